@@ -0,0 +1,134 @@
+//! Indices of the client files next to the design data: Lua, video and
+//! audio. Each kind has its own `M_<Kind>V.bytes` mini asset with the same
+//! header as `M_DesignV.bytes`, naming a `<Kind>V_<hash>.bytes` index of one
+//! JSON object per line. The binary asset bundle and block indices aren't
+//! read.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    Lua,
+    Video,
+    Audio,
+}
+
+impl IndexKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lua => "Lua",
+            Self::Video => "Video",
+            Self::Audio => "Audio",
+        }
+    }
+
+    /// Folder under the platform root holding the mini asset, the index and
+    /// the files it lists.
+    pub fn folder(self) -> &'static str {
+        match self {
+            Self::Lua => "Lua",
+            Self::Video => "Video",
+            Self::Audio => "AudioBlock",
+        }
+    }
+
+    /// Path of a file of this kind under the platform root.
+    pub fn path(self, name: &str) -> String {
+        format!("{}/{name}", self.folder())
+    }
+
+    pub fn mini_asset_path(self) -> String {
+        self.path(&format!("M_{}V.bytes", self.name()))
+    }
+
+    pub fn index_path(self, index_hash: &str) -> String {
+        self.path(&format!("{}V_{index_hash}.bytes", self.name()))
+    }
+}
+
+/// A file listed by an index.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssetEntry {
+    #[serde(
+        alias = "name",
+        alias = "Path",
+        alias = "remoteName",
+        alias = "RemoteName"
+    )]
+    pub name: String,
+    #[serde(alias = "size", alias = "fileSize", alias = "FileSize")]
+    pub size: u64,
+    #[serde(default, alias = "md5", alias = "MD5")]
+    pub md5: Option<String>,
+    /// Whether the file only comes with a patch rather than the full client.
+    #[serde(default, alias = "patch", alias = "IsPatch")]
+    pub patch: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetIndex {
+    pub kind: &'static str,
+    pub entries: Vec<AssetEntry>,
+}
+
+impl AssetIndex {
+    /// Reads an index of one JSON entry per line. Anything that isn't an
+    /// entry is an error, so a changed format doesn't go unnoticed.
+    pub fn parse(kind: IndexKind, bytes: &[u8]) -> io::Result<Self> {
+        Ok(Self {
+            kind: kind.name(),
+            entries: parse_json_lines(kind, bytes)?,
+        })
+    }
+}
+
+fn parse_json_lines(kind: IndexKind, bytes: &[u8]) -> io::Result<Vec<AssetEntry>> {
+    let text = std::str::from_utf8(bytes).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} index isn't text", kind.name()),
+        )
+    })?;
+
+    text.lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} of the {} index: {e}", i + 1, kind.name()),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetIndex, IndexKind};
+
+    #[test]
+    fn test_parse_asset_index() {
+        const INDEX: &str = concat!(
+            r#"{"Name":"Video/Intro.usm","Size":1024,"Md5":"00112233445566778899aabbccddeeff","Patch":false}"#,
+            "\n\n",
+            r#"{"remoteName":"Cutscene.usm","fileSize":2048,"md5":"ffeeddccbbaa99887766554433221100","Patch":true,"TaskIds":[]}"#,
+            "\n"
+        );
+
+        let index = AssetIndex::parse(IndexKind::Video, INDEX.as_bytes()).unwrap();
+        assert_eq!(index.entries.len(), 2);
+        assert_eq!(index.entries[1].name, "Cutscene.usm");
+        assert_eq!(index.entries[1].size, 2048);
+        assert!(index.entries[1].patch);
+
+        assert!(AssetIndex::parse(IndexKind::Video, b"{\"Size\":1}").is_err());
+        assert!(AssetIndex::parse(IndexKind::Audio, &[0xff, 0, 1]).is_err());
+        assert_eq!(IndexKind::Video.mini_asset_path(), "Video/M_VideoV.bytes");
+        assert_eq!(IndexKind::Lua.index_path("ab"), "Lua/LuaV_ab.bytes");
+    }
+}
@@ -0,0 +1,163 @@
+use byteorder::{LE, ReadBytesExt};
+use serde::Serialize;
+use std::io::{self, Cursor};
+use tg_bytes_util::FromBytes;
+use varint_rs::VarintReader;
+
+/// Header in front of the rows of a `BakedConfig/ExcelOutput/*.bytes` blob,
+/// TextMaps included.
+///
+/// ```text
+/// varint      serializer version, 0 so far
+/// zigzag i64  row count, the length prefix of the row array
+/// rows...
+/// ```
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExcelHeader {
+    pub version: u32,
+    pub row_count: u32,
+}
+
+impl ExcelHeader {
+    /// Versions the layout above was checked against.
+    pub const KNOWN_VERSIONS: &[u32] = &[0];
+
+    /// Reads the header off `bytes`, returning it with the row array, which
+    /// still starts with its row count. A blob of another version or with
+    /// more rows than bytes is an error rather than rows read from the wrong
+    /// place. An empty blob is an empty table.
+    pub fn split(bytes: &[u8]) -> io::Result<(Self, &[u8])> {
+        if bytes.is_empty() {
+            let header = Self {
+                version: 0,
+                row_count: 0,
+            };
+            return Ok((header, bytes));
+        }
+
+        let mut r = Cursor::new(bytes);
+        let version = r.read_u32_varint()?;
+        if !Self::KNOWN_VERSIONS.contains(&version) {
+            return Err(invalid(format!("unknown ExcelOutput version {version}")));
+        }
+
+        let rows = r.position() as usize;
+        let row_count = r.read_i64_varint()?;
+        // every row takes at least its exist flags byte
+        let left = bytes.len() - r.position() as usize;
+        match u32::try_from(row_count) {
+            Ok(row_count) if row_count as usize <= left => {
+                Ok((Self { version, row_count }, &bytes[rows..]))
+            }
+            _ => Err(invalid(format!(
+                "ExcelOutput row count {row_count} doesn't fit its {left} bytes"
+            ))),
+        }
+    }
+}
+
+/// 12-byte header in front of some `BakedConfig/Config/*.bytes` blobs (e.g.
+/// AudioConfig), three little-endian `u32`s. Blobs without it start right
+/// away with the config's exist flags.
+///
+/// There's no marker telling the two apart, so a header is assumed when the
+/// last field matches the size of what follows. That's a heuristic: a config
+/// whose first bytes happen to match is misread. The meaning of the first two
+/// fields is a guess, only the payload size has been checked against blobs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConfigHeader {
+    /// Assumed to be a format version.
+    pub version: u32,
+    /// Assumed to be flags.
+    pub flags: u32,
+    /// Size of the payload following the header, which is what tells a
+    /// header apart from a config without one.
+    pub payload_size: u32,
+}
+
+impl ConfigHeader {
+    pub const SIZE: usize = 12;
+    /// Versions taken to be this layout, only as far as the assumption above
+    /// goes.
+    pub const KNOWN_VERSIONS: &[u32] = &[1];
+
+    /// Splits the header off `bytes` when its payload size matches what
+    /// follows it, otherwise the blob is returned whole. A header of an
+    /// unknown version is an error.
+    pub fn split(bytes: &[u8]) -> io::Result<(Option<Self>, &[u8])> {
+        if bytes.len() < Self::SIZE {
+            return Ok((None, bytes));
+        }
+
+        let header = Self::from_bytes(&mut Cursor::new(bytes))?;
+        if header.payload_size as usize != bytes.len() - Self::SIZE {
+            return Ok((None, bytes));
+        }
+        if !Self::KNOWN_VERSIONS.contains(&header.version) {
+            return Err(invalid(format!(
+                "unknown Config header version {} (flags {:#x})",
+                header.version, header.flags
+            )));
+        }
+
+        Ok((Some(header), &bytes[Self::SIZE..]))
+    }
+}
+
+impl FromBytes for ConfigHeader {
+    fn from_bytes<T: std::io::Seek + std::io::Read>(r: &mut T) -> std::io::Result<Self> {
+        Ok(Self {
+            version: r.read_u32::<LE>()?,
+            flags: r.read_u32::<LE>()?,
+            payload_size: r.read_u32::<LE>()?,
+        })
+    }
+}
+
+/// Header of either kind, as put into per-file reports.
+#[derive(Debug, Clone, Serialize)]
+pub enum BakedHeader {
+    Excel(ExcelHeader),
+    Config(ConfigHeader),
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baked_header::{ConfigHeader, ExcelHeader};
+
+    #[test]
+    fn test_split_headers() {
+        let (header, rows) = ExcelHeader::split(&[0, 4, 1, 1]).unwrap();
+        assert_eq!(0, header.version);
+        assert_eq!(2, header.row_count);
+        assert_eq!(&[4, 1, 1], rows);
+
+        // another version, a negative row count, more rows than bytes
+        assert!(ExcelHeader::split(&[2, 2, 1]).is_err());
+        assert!(ExcelHeader::split(&[0, 1]).is_err());
+        assert!(ExcelHeader::split(&[0, 6, 1]).is_err());
+        // an empty blob is an empty table, as it always was
+        let (header, rows) = ExcelHeader::split(&[]).unwrap();
+        assert_eq!(0, header.row_count);
+        assert!(rows.is_empty());
+
+        let bytes = [1, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 7, 8];
+        let (header, payload) = ConfigHeader::split(&bytes).unwrap();
+        assert_eq!(2, header.unwrap().flags);
+        assert_eq!(&[7, 8], payload);
+
+        let (header, payload) = ConfigHeader::split(&bytes[..13]).unwrap();
+        assert!(header.is_none());
+        assert_eq!(13, payload.len());
+
+        let mut bytes = bytes;
+        bytes[0] = 9;
+        assert!(ConfigHeader::split(&bytes).is_err());
+    }
+}
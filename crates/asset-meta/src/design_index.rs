@@ -1,5 +1,8 @@
-use byteorder::{BE, ReadBytesExt};
-use std::fmt::Write;
+use byteorder::{BE, ReadBytesExt, WriteBytesExt};
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+};
 use tg_bytes_util::FromBytes;
 
 #[derive(Debug)]
@@ -27,6 +30,18 @@ impl FromBytes for DesignIndex {
     }
 }
 
+impl DesignIndex {
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_i64::<BE>(self.unk_i64)?;
+        w.write_i32::<BE>(self.file_list.len() as i32)?;
+        w.write_i32::<BE>(self.design_data_count)?;
+        for file_entry in &self.file_list {
+            file_entry.write(w)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct FileEntry {
     pub name_hash: i32,
@@ -65,6 +80,39 @@ impl FromBytes for FileEntry {
     }
 }
 
+impl FileEntry {
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_i32::<BE>(self.name_hash)?;
+        w.write_all(&parse_byte_name(&self.file_byte_name)?)?;
+        w.write_i64::<BE>(self.size)?;
+        w.write_i32::<BE>(self.data_entries.len() as i32)?;
+        for data_entry in &self.data_entries {
+            data_entry.write(w)?;
+        }
+        w.write_u8(self.unk)
+    }
+}
+
+/// The 16 bytes of a `file_byte_name` written as hex.
+fn parse_byte_name(name: &str) -> io::Result<[u8; 16]> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("file byte name {name} isn't 32 hex digits"),
+        )
+    };
+    if name.len() != 32 {
+        return Err(invalid());
+    }
+
+    let mut bytes = [0; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(name.get(i * 2..i * 2 + 2).ok_or_else(invalid)?, 16)
+            .map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
 #[derive(Debug)]
 pub struct DataEntry {
     pub name_hash: i32,
@@ -82,6 +130,14 @@ impl FromBytes for DataEntry {
     }
 }
 
+impl DataEntry {
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_i32::<BE>(self.name_hash)?;
+        w.write_u32::<BE>(self.size)?;
+        w.write_u32::<BE>(self.offset)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::design_index::DesignIndex;
@@ -113,4 +169,14 @@ mod tests {
         assert_eq!(89899, parsed.file_list[0].data_entries[0].size);
         assert_eq!(0, parsed.file_list[0].data_entries[0].offset);
     }
+
+    #[test]
+    fn test_write_design_index() {
+        const BYTES: &[u8] = include_bytes!("../tests/DesignV.bytes");
+
+        let parsed = DesignIndex::from_bytes(&mut Cursor::new(BYTES)).unwrap();
+        let mut written = Vec::with_capacity(BYTES.len());
+        parsed.write(&mut written).unwrap();
+        assert!(written == BYTES);
+    }
 }
@@ -0,0 +1,185 @@
+//! Checks of design data blocks against their `FileEntry` in the design
+//! index.
+
+use crate::design_index::{DataEntry, FileEntry};
+use std::fmt::{self, Display, Write as _};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    /// The block isn't as long as the index says.
+    SizeMismatch {
+        block: String,
+        expected: u64,
+        actual: u64,
+    },
+    /// The block's digest isn't its name.
+    DigestMismatch { block: String, actual: String },
+    /// A data entry reaches past the end of the block.
+    EntryOutOfBounds {
+        block: String,
+        name_hash: i32,
+        offset: u32,
+        size: u32,
+        block_len: u64,
+    },
+}
+
+impl Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SizeMismatch {
+                block,
+                expected,
+                actual,
+            } => write!(f, "{block} is {actual} bytes, expected {expected}"),
+            Self::DigestMismatch { block, actual } => {
+                write!(f, "{block} has digest {actual}")
+            }
+            Self::EntryOutOfBounds {
+                block,
+                name_hash,
+                offset,
+                size,
+                block_len,
+            } => write!(
+                f,
+                "entry {name_hash} at {offset}+{size} is past the end of {block} ({block_len} bytes)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+impl BlockError {
+    /// Name of the block that failed.
+    pub fn block(&self) -> &str {
+        match self {
+            Self::SizeMismatch { block, .. }
+            | Self::DigestMismatch { block, .. }
+            | Self::EntryOutOfBounds { block, .. } => block,
+        }
+    }
+}
+
+/// Whether block names are the MD5 of their content, judged from the blocks
+/// whose digest was checked. Names look like MD5s, but when not a single one
+/// matches, they're taken to be some other hash and mismatches aren't errors.
+pub fn names_are_digests(checked: usize, mismatched: usize) -> bool {
+    mismatched < checked
+}
+
+/// MD5 of a block, in the same form as `FileEntry::file_byte_name`.
+pub fn block_digest(data: &[u8]) -> String {
+    md5::compute(data)
+        .iter()
+        .fold(String::with_capacity(32), |mut output, b| {
+            let _ = write!(output, "{b:02x}");
+            output
+        })
+}
+
+impl FileEntry {
+    pub fn check_size(&self, data: &[u8]) -> Result<(), BlockError> {
+        if data.len() as u64 != self.size as u64 {
+            return Err(BlockError::SizeMismatch {
+                block: self.file_byte_name.clone(),
+                expected: self.size as u64,
+                actual: data.len() as u64,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn check_digest(&self, data: &[u8]) -> Result<(), BlockError> {
+        let actual = block_digest(data);
+        if actual != self.file_byte_name {
+            return Err(BlockError::DigestMismatch {
+                block: self.file_byte_name.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks every data entry fits in a block of `block_len` bytes.
+    pub fn check_entries(&self, block_len: u64) -> Result<(), BlockError> {
+        self.data_entries
+            .iter()
+            .try_for_each(|entry| self.entry_range(entry, block_len).map(|_| ()))
+    }
+
+    /// The bytes of one of this file's data entries.
+    pub fn entry_data<'a>(
+        &self,
+        entry: &DataEntry,
+        data: &'a [u8],
+    ) -> Result<&'a [u8], BlockError> {
+        let (start, end) = self.entry_range(entry, data.len() as u64)?;
+        Ok(&data[start..end])
+    }
+
+    fn entry_range(&self, entry: &DataEntry, block_len: u64) -> Result<(usize, usize), BlockError> {
+        let end = entry.offset as u64 + entry.size as u64;
+        if end > block_len {
+            return Err(BlockError::EntryOutOfBounds {
+                block: self.file_byte_name.clone(),
+                name_hash: entry.name_hash,
+                offset: entry.offset,
+                size: entry.size,
+                block_len,
+            });
+        }
+        Ok((entry.offset as usize, end as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockError, block_digest};
+    use crate::design_index::{DataEntry, FileEntry};
+
+    #[test]
+    fn test_verify_block() {
+        let data = b"0123456789";
+        let entry = FileEntry {
+            name_hash: 0,
+            file_byte_name: block_digest(data),
+            size: 10,
+            data_count: 2,
+            data_entries: vec![
+                DataEntry {
+                    name_hash: 1,
+                    size: 4,
+                    offset: 0,
+                },
+                DataEntry {
+                    name_hash: 2,
+                    size: 8,
+                    offset: 4,
+                },
+            ],
+            unk: 0,
+        };
+
+        assert_eq!(entry.file_byte_name, "781e5e245d69b566979b86e28d23f2c7");
+        assert!(entry.check_size(data).is_ok());
+        assert!(entry.check_digest(data).is_ok());
+        assert!(matches!(
+            entry.check_size(&data[..9]),
+            Err(BlockError::SizeMismatch { actual: 9, .. })
+        ));
+        assert!(entry.check_digest(b"012345678_").is_err());
+
+        assert_eq!(
+            entry.entry_data(&entry.data_entries[0], data).unwrap(),
+            b"0123"
+        );
+        assert!(matches!(
+            entry.entry_data(&entry.data_entries[1], data),
+            Err(BlockError::EntryOutOfBounds { name_hash: 2, .. })
+        ));
+        assert!(entry.check_entries(10).is_err());
+        assert!(entry.check_entries(12).is_ok());
+    }
+}
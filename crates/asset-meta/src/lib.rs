@@ -1,2 +1,6 @@
+pub mod asset_index;
+pub mod baked_header;
 pub mod design_index;
+pub mod integrity;
 pub mod mini_asset;
+pub mod repack;
@@ -1,42 +1,208 @@
-use std::io::SeekFrom;
+use std::io::{self, Read, Write};
 
-use byteorder::{LE, ReadBytesExt};
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+use serde::Serialize;
 use tg_bytes_util::{ByteHash16, FromBytes};
 
-#[derive(Debug)]
+/// Header of an `M_*V.bytes` file, naming the index of one revision.
+///
+/// ```text
+/// 0x00  magic "SRMI"
+/// 0x04  version, 4 bytes
+/// 0x08  u32 size of the whole file
+/// 0x0c  u16, u16, u32, u32 not understood yet
+/// 0x18  u32 revision id
+/// 0x1c  16-byte hash naming the index file
+/// 0x2c  u64 size of the index file
+/// 0x34  u64 unix timestamp
+/// 0x3c  trailing bytes up to the size, zeroes so far
+/// ```
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct MiniAsset {
+    pub version: [u8; 4],
+    pub size: u32,
+    pub unk_0c: u16,
+    pub unk_0e: u16,
+    pub unk_10: u32,
+    pub unk_14: u32,
     pub revision_id: u32,
+    #[serde(serialize_with = "serialize_hash")]
     pub design_index_hash: ByteHash16,
+    pub design_index_size: u64,
+    pub timestamp: u64,
+    pub trailing: Vec<u8>,
+}
+
+impl MiniAsset {
+    pub const MAGIC: [u8; 4] = *b"SRMI";
+    /// Versions the layout above was checked against.
+    pub const KNOWN_VERSIONS: &[[u8; 4]] = &[[0, 3, 0, 1]];
+    /// Size of the fields before the trailing bytes.
+    pub const FIXED_SIZE: u32 = 0x3c;
+
+    /// A header of the latest known version naming an index, with the
+    /// unknown fields as the client has them.
+    pub fn new(revision_id: u32, design_index_hash: ByteHash16, design_index_size: u64) -> Self {
+        Self {
+            version: Self::KNOWN_VERSIONS[Self::KNOWN_VERSIONS.len() - 1],
+            size: Self::FIXED_SIZE + 6,
+            unk_0c: 0,
+            unk_0e: 12,
+            unk_10: 3,
+            unk_14: 2,
+            revision_id,
+            design_index_hash,
+            design_index_size,
+            timestamp: 0,
+            trailing: vec![0; 6],
+        }
+    }
+
+    pub fn version_string(&self) -> String {
+        self.version.map(|v| v.to_string()).join(".")
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        if self.size != Self::FIXED_SIZE + self.trailing.len() as u32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "mini asset size {} doesn't match its {} trailing bytes",
+                    self.size,
+                    self.trailing.len()
+                ),
+            ));
+        }
+
+        w.write_all(&Self::MAGIC)?;
+        w.write_all(&self.version)?;
+        w.write_u32::<LE>(self.size)?;
+        w.write_u16::<LE>(self.unk_0c)?;
+        w.write_u16::<LE>(self.unk_0e)?;
+        w.write_u32::<LE>(self.unk_10)?;
+        w.write_u32::<LE>(self.unk_14)?;
+        w.write_u32::<LE>(self.revision_id)?;
+        self.design_index_hash.write(w)?;
+        w.write_u64::<LE>(self.design_index_size)?;
+        w.write_u64::<LE>(self.timestamp)?;
+        w.write_all(&self.trailing)
+    }
 }
 
 impl FromBytes for MiniAsset {
     fn from_bytes<T: std::io::Seek + std::io::Read>(r: &mut T) -> std::io::Result<Self> {
-        r.seek(SeekFrom::Current(6 * 4))?;
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(invalid(format!("not a mini asset, magic is {magic:02x?}")));
+        }
+
+        let mut version = [0; 4];
+        r.read_exact(&mut version)?;
+        if !Self::KNOWN_VERSIONS.contains(&version) {
+            return Err(invalid(format!(
+                "unknown mini asset version {}",
+                version.map(|v| v.to_string()).join(".")
+            )));
+        }
+
+        let size = r.read_u32::<LE>()?;
+        if size < Self::FIXED_SIZE {
+            return Err(invalid(format!("mini asset size {size} is too small")));
+        }
+
         Ok(Self {
+            version,
+            size,
+            unk_0c: r.read_u16::<LE>()?,
+            unk_0e: r.read_u16::<LE>()?,
+            unk_10: r.read_u32::<LE>()?,
+            unk_14: r.read_u32::<LE>()?,
             revision_id: r.read_u32::<LE>()?,
             design_index_hash: ByteHash16::from_bytes(r)?,
+            design_index_size: r.read_u64::<LE>()?,
+            timestamp: r.read_u64::<LE>()?,
+            trailing: {
+                let mut trailing = Vec::new();
+                r.take((size - Self::FIXED_SIZE) as u64)
+                    .read_to_end(&mut trailing)?;
+                if trailing.len() != (size - Self::FIXED_SIZE) as usize {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("mini asset is shorter than its size {size}"),
+                    ));
+                }
+                trailing
+            },
         })
     }
 }
 
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn serialize_hash<S: serde::Serializer>(hash: &ByteHash16, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(hash)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::mini_asset::MiniAsset;
     use std::io::Cursor;
     use tg_bytes_util::FromBytes;
 
+    const BYTES: &[u8] = &[
+        83, 82, 77, 73, 0, 3, 0, 1, 66, 0, 0, 0, 0, 0, 12, 0, 3, 0, 0, 0, 2, 0, 0, 0, 234, 255,
+        151, 0, 202, 110, 28, 223, 138, 63, 212, 4, 63, 130, 138, 178, 68, 22, 219, 131, 234, 55,
+        0, 0, 0, 0, 0, 0, 210, 249, 237, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+
     #[test]
     fn test() {
-        const BYTES: &[u8] = &[
-            83, 82, 77, 73, 0, 3, 0, 1, 66, 0, 0, 0, 0, 0, 12, 0, 3, 0, 0, 0, 2, 0, 0, 0, 234, 255,
-            151, 0, 202, 110, 28, 223, 138, 63, 212, 4, 63, 130, 138, 178, 68, 22, 219, 131, 234,
-            55, 0, 0, 0, 0, 0, 0, 210, 249, 237, 103, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ];
         let mut r = Cursor::new(BYTES);
         let parsed = MiniAsset::from_bytes(&mut r).unwrap();
         assert_eq!(
             parsed.design_index_hash.to_string(),
             "df1c6eca04d43f8ab28a823f83db1644"
+        );
+        assert_eq!(parsed.version_string(), "0.3.0.1");
+        assert_eq!(parsed.revision_id, 9961450);
+        assert_eq!(parsed.design_index_size, 14314);
+        assert_eq!(parsed.timestamp, 1743649234);
+        assert_eq!(parsed.trailing, [0; 6]);
+        assert_eq!(r.position(), BYTES.len() as u64);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let parsed = MiniAsset::from_bytes(&mut Cursor::new(BYTES)).unwrap();
+        let mut written = Vec::new();
+        parsed.write(&mut written).unwrap();
+        assert_eq!(written, BYTES);
+
+        let mut new = Vec::new();
+        MiniAsset::new(
+            parsed.revision_id,
+            parsed.design_index_hash.clone(),
+            parsed.design_index_size,
         )
+        .write(&mut new)
+        .unwrap();
+        assert_eq!(new[..0x34], BYTES[..0x34]);
+    }
+
+    #[test]
+    fn test_rejects_unknown_format() {
+        let mut bytes = BYTES.to_vec();
+        bytes[0] = b'X';
+        assert!(MiniAsset::from_bytes(&mut Cursor::new(&bytes)).is_err());
+
+        let mut bytes = BYTES.to_vec();
+        bytes[7] = 2;
+        assert!(MiniAsset::from_bytes(&mut Cursor::new(&bytes)).is_err());
+
+        assert!(MiniAsset::from_bytes(&mut Cursor::new(&BYTES[..40])).is_err());
     }
 }
@@ -0,0 +1,224 @@
+//! Builds a design data set out of assets: blocks named by their content, the
+//! `DesignV_<hash>.bytes` index of them and the `M_DesignV.bytes` naming it.
+
+use crate::{
+    design_index::{DataEntry, DesignIndex, FileEntry},
+    integrity::block_digest,
+    mini_asset::MiniAsset,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+use tg_bytes_util::ByteHash16;
+
+#[derive(Debug, Clone)]
+pub struct RepackOptions {
+    pub revision_id: u32,
+    pub timestamp: u64,
+    /// Assets are packed into a block until it would grow past this, bigger
+    /// assets get a block of their own.
+    pub max_block_size: u64,
+    /// Assets that always get a block of their own, e.g. the ConfigManifest,
+    /// which is read as the whole block.
+    pub own_block: Vec<i32>,
+}
+
+impl Default for RepackOptions {
+    fn default() -> Self {
+        Self {
+            revision_id: 0,
+            timestamp: 0,
+            max_block_size: 16 << 20,
+            own_block: Vec::new(),
+        }
+    }
+}
+
+pub struct Repacked {
+    pub mini_asset: MiniAsset,
+    pub design_index: DesignIndex,
+    /// Blocks by `file_byte_name`, in index order.
+    pub blocks: Vec<(String, Vec<u8>)>,
+}
+
+/// Lays `assets` out into blocks, ordered by name hash so the same assets
+/// always give the same data set.
+pub fn repack(assets: &HashMap<i32, Vec<u8>>, options: &RepackOptions) -> io::Result<Repacked> {
+    let mut name_hashes = assets.keys().copied().collect::<Vec<_>>();
+    name_hashes.sort_by_key(|name_hash| (!options.own_block.contains(name_hash), *name_hash));
+
+    let mut groups: Vec<Vec<i32>> = Vec::new();
+    let mut group_size = 0;
+    for name_hash in name_hashes {
+        let size = assets[&name_hash].len() as u64;
+        let alone = options.own_block.contains(&name_hash);
+        let fits = groups.last().is_some_and(|group| {
+            !options.own_block.contains(&group[0]) && group_size + size <= options.max_block_size
+        });
+        if alone || !fits {
+            groups.push(Vec::new());
+            group_size = 0;
+        }
+        groups.last_mut().unwrap().push(name_hash);
+        group_size += size;
+    }
+
+    let mut blocks = Vec::with_capacity(groups.len());
+    let mut file_list = Vec::with_capacity(groups.len());
+    for group in groups {
+        let mut data = Vec::new();
+        let mut data_entries = Vec::with_capacity(group.len());
+        for name_hash in &group {
+            let bytes = &assets[name_hash];
+            data_entries.push(DataEntry {
+                name_hash: *name_hash,
+                size: to_u32(bytes.len())?,
+                offset: to_u32(data.len())?,
+            });
+            data.extend_from_slice(bytes);
+        }
+
+        let file_byte_name = block_digest(&data);
+        file_list.push(FileEntry {
+            // the client names a block after its first entry
+            name_hash: group[0],
+            file_byte_name: file_byte_name.clone(),
+            size: data.len() as i64,
+            data_count: data_entries.len() as i32,
+            data_entries,
+            unk: 0x80,
+        });
+        blocks.push((file_byte_name, data));
+    }
+
+    let design_index = DesignIndex {
+        // as in every index seen so far
+        unk_i64: 0xff_0000_0001,
+        file_count: file_list.len() as i32,
+        design_data_count: assets.len() as i32,
+        file_list,
+    };
+
+    let mut index_bytes = Vec::new();
+    design_index.write(&mut index_bytes)?;
+    let mut mini_asset = MiniAsset::new(
+        options.revision_id,
+        ByteHash16::new(md5::compute(&index_bytes).0),
+        index_bytes.len() as u64,
+    );
+    mini_asset.timestamp = options.timestamp;
+
+    Ok(Repacked {
+        mini_asset,
+        design_index,
+        blocks,
+    })
+}
+
+impl Repacked {
+    /// Every file of the data set by name, `M_DesignV.bytes` first.
+    pub fn files(&self) -> io::Result<Vec<(String, Vec<u8>)>> {
+        let mut mini_asset = Vec::new();
+        self.mini_asset.write(&mut mini_asset)?;
+        let mut design_index = Vec::new();
+        self.design_index.write(&mut design_index)?;
+
+        Ok([
+            (String::from("M_DesignV.bytes"), mini_asset),
+            (
+                format!("DesignV_{}.bytes", self.mini_asset.design_index_hash),
+                design_index,
+            ),
+        ]
+        .into_iter()
+        .chain(
+            self.blocks
+                .iter()
+                .map(|(name, data)| (format!("{name}.bytes"), data.clone())),
+        )
+        .collect())
+    }
+
+    /// Writes the data set into `dir`, laid out like a Persistent folder.
+    pub fn write_to(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (name, data) in self.files()? {
+            fs::File::create(dir.join(name))?.write_all(&data)?;
+        }
+        Ok(())
+    }
+}
+
+fn to_u32(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{len} bytes don't fit a data entry"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RepackOptions, repack};
+    use crate::{design_index::DesignIndex, mini_asset::MiniAsset};
+    use std::{collections::HashMap, io::Cursor};
+    use tg_bytes_util::FromBytes;
+
+    #[test]
+    fn test_repack() {
+        let assets = HashMap::from([
+            (3, b"manifest".to_vec()),
+            (1, vec![1; 6]),
+            (2, vec![2; 6]),
+            (4, vec![4; 20]),
+        ]);
+        let repacked = repack(
+            &assets,
+            &RepackOptions {
+                revision_id: 7,
+                max_block_size: 12,
+                own_block: vec![3],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let files = repacked.files().unwrap();
+        let file = |name: &str| &files.iter().find(|(n, _)| n == name).unwrap().1;
+
+        let mini_asset = MiniAsset::from_bytes(&mut Cursor::new(file("M_DesignV.bytes"))).unwrap();
+        assert_eq!(mini_asset.revision_id, 7);
+        let index_bytes = file(&format!("DesignV_{}.bytes", mini_asset.design_index_hash));
+        assert_eq!(index_bytes.len() as u64, mini_asset.design_index_size);
+
+        let design_index = DesignIndex::from_bytes(&mut Cursor::new(index_bytes)).unwrap();
+        let layout = design_index
+            .file_list
+            .iter()
+            .map(|file_entry| {
+                file_entry
+                    .data_entries
+                    .iter()
+                    .map(|entry| entry.name_hash)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(layout, [vec![3], vec![1, 2], vec![4]]);
+
+        for file_entry in &design_index.file_list {
+            let data = file(&format!("{}.bytes", file_entry.file_byte_name));
+            file_entry.check_size(data).unwrap();
+            file_entry.check_digest(data).unwrap();
+            for entry in &file_entry.data_entries {
+                assert_eq!(
+                    file_entry.entry_data(entry, data).unwrap(),
+                    assets[&entry.name_hash]
+                );
+            }
+        }
+    }
+}
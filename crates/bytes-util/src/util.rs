@@ -55,9 +55,29 @@ impl ExistFlag {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ByteHash16(Vec<u8>);
 
+impl ByteHash16 {
+    /// A hash from its bytes in display order.
+    pub fn new(bytes: [u8; 16]) -> Self {
+        Self(bytes.to_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Writes the hash the way [`FromBytes`] reads it, each 4-byte chunk
+    /// reversed.
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for chunk in self.0.chunks(4) {
+            writer.write_all(&chunk.iter().rev().copied().collect::<Vec<_>>())?;
+        }
+        Ok(())
+    }
+}
+
 impl FromBytes for ByteHash16 {
     fn from_bytes<T: std::io::Seek + std::io::Read>(r: &mut T) -> std::io::Result<Self> {
         let mut full_hash = [0u8; 16];
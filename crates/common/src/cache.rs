@@ -0,0 +1,287 @@
+//! On-disk cache of design data blocks. Design indices and `FileEntry` files
+//! are named by their content, so a block once downloaded never changes and
+//! is shared by every revision that still uses it.
+//!
+//! ```text
+//! <cache dir>/
+//!   blocks/<file_byte_name>.bytes          <- and <Kind>V_<hash>.bytes, <md5>.bytes
+//!   mini/<xxh64 of the source>/M_*V.bytes   <- last ones seen, for --offline
+//! ```
+
+use crate::{hash, source::AssetSource};
+use anyhow::{Context as _, Result};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tg_asset_meta::integrity::block_digest;
+
+pub struct BlockCache {
+    dir: PathBuf,
+}
+
+impl BlockCache {
+    pub fn new(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir.join("blocks"))
+            .context(format!("Failed to create cache {dir:?}"))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn block_path(&self, name: &str) -> PathBuf {
+        self.dir.join("blocks").join(name)
+    }
+
+    fn mini_asset_path(&self, source: &str, name: &str) -> PathBuf {
+        self.dir
+            .join("mini")
+            .join(format!("{:016x}", hash::get_64bit_hash_const(source)))
+            .join(name)
+    }
+
+    /// A cached block, if there's one of the right size. Hits are touched so
+    /// pruning drops the least recently used blocks first.
+    pub fn get(&self, name: &str, size: Option<u64>) -> Option<Vec<u8>> {
+        let path = self.block_path(name);
+        let data = fs::read(&path).ok()?;
+        if size.is_some_and(|size| size != data.len() as u64) {
+            tracing::warn!("Cached {name} has the wrong size, downloading it again");
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        if let Ok(file) = File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(data)
+    }
+
+    pub fn put(&self, name: &str, data: &[u8]) -> Result<()> {
+        write_atomic(&self.block_path(name), data)
+    }
+
+    /// Drops the least recently used blocks until the cache fits in
+    /// `max_size` bytes. Returns the number of bytes freed.
+    pub fn prune(&self, max_size: u64) -> Result<u64> {
+        let mut blocks = Vec::new();
+        for entry in fs::read_dir(self.dir.join("blocks"))? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                blocks.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        blocks.sort();
+
+        let mut total = blocks.iter().map(|(_, len, _)| len).sum::<u64>();
+        let mut freed = 0;
+        for (_, len, path) in blocks {
+            if total <= max_size {
+                break;
+            }
+            fs::remove_file(&path)?;
+            total -= len;
+            freed += len;
+        }
+
+        Ok(freed)
+    }
+}
+
+#[inline]
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // blocks are read by several threads, never let one see a partial file
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Whether a block named by its MD5, `<file_byte_name>.bytes`, still has it.
+/// Other names have nothing to check against.
+fn has_own_digest(name: &str, data: &[u8]) -> bool {
+    match name.strip_suffix(".bytes") {
+        Some(digest) if is_digest(digest) => block_digest(data).eq_ignore_ascii_case(digest),
+        _ => true,
+    }
+}
+
+#[inline]
+fn is_digest(name: &str) -> bool {
+    name.len() == 32 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// A remote source read through a [`BlockCache`]. Offline, nothing is
+/// downloaded and `M_DesignV.bytes` and the other mini assets come from the
+/// last online run.
+pub struct CachedSource {
+    inner: Box<dyn AssetSource>,
+    cache: BlockCache,
+    offline: bool,
+}
+
+impl CachedSource {
+    pub fn new(inner: Box<dyn AssetSource>, cache: BlockCache, offline: bool) -> Self {
+        Self {
+            inner,
+            cache,
+            offline,
+        }
+    }
+
+    /// Reads `key` from the cache, or fetches and caches it when it has the
+    /// expected size and, for a name that is a digest, that digest.
+    fn read_cached(
+        &self,
+        key: &str,
+        size: Option<u64>,
+        fetch: impl FnOnce() -> Result<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        if let Some(data) = self.cache.get(key, size) {
+            return Ok(data);
+        } else if self.offline {
+            anyhow::bail!("Offline and {key} isn't cached");
+        }
+
+        let data = fetch()?;
+        match size {
+            Some(size) if size != data.len() as u64 => tracing::warn!(
+                "{key} is {} bytes but the index says {size}, not caching it",
+                data.len()
+            ),
+            _ if !has_own_digest(key, &data) => {
+                tracing::warn!("{key} doesn't match its digest, not caching it")
+            }
+            _ => self.cache.put(key, &data)?,
+        }
+        Ok(data)
+    }
+}
+
+impl AssetSource for CachedSource {
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        // M_DesignV.bytes, or M_VideoV.bytes and the like in their folder
+        let mini_asset = name
+            .rsplit('/')
+            .next()
+            .filter(|file_name| file_name.starts_with("M_") && file_name.ends_with("V.bytes"));
+        let mini_asset_path = mini_asset.map(|file_name| {
+            self.cache
+                .mini_asset_path(&self.inner.describe(), file_name)
+        });
+
+        if self.offline {
+            if let Some(mini_asset_path) = &mini_asset_path {
+                return fs::read(mini_asset_path).context(format!(
+                    "Offline and {name} of {} isn't cached",
+                    self.describe()
+                ));
+            }
+            anyhow::bail!("Offline and {name} isn't cached");
+        }
+
+        let data = self.inner.read(name)?;
+        if let Some(mini_asset_path) = &mini_asset_path {
+            write_atomic(mini_asset_path, &data)?;
+        }
+        Ok(data)
+    }
+
+    fn read_block(&self, name: &str, size: Option<u64>) -> Result<Vec<u8>> {
+        // blocks are named by their content wherever they are, e.g.
+        // Video/VideoV_<hash>.bytes, so they're kept side by side
+        let key = name.rsplit('/').next().unwrap_or(name);
+        self.read_cached(key, size, || self.inner.read_block(name, size))
+    }
+
+    fn read_digest(&self, name: &str, digest: &str, size: Option<u64>) -> Result<Vec<u8>> {
+        if !is_digest(digest) {
+            return self.inner.read_digest(name, digest, size);
+        }
+        let key = format!("{}.bytes", digest.to_ascii_lowercase());
+        self.read_cached(&key, size, || self.inner.read_digest(name, digest, size))
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn is_remote(&self) -> bool {
+        !self.offline
+    }
+
+    fn concurrency(&self) -> usize {
+        self.inner.concurrency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockCache, CachedSource};
+    use crate::source::{AssetSource, MemorySource};
+    use std::fs;
+    use tg_asset_meta::integrity::block_digest;
+
+    #[test]
+    fn test_cached_source() {
+        let dir = std::env::temp_dir().join(format!("tg-cache-test-{}", std::process::id()));
+        let mut memory = MemorySource::default();
+        memory
+            .files
+            .insert(String::from("M_DesignV.bytes"), b"mini".to_vec());
+        memory.files.insert(String::from("a.bytes"), vec![0; 4]);
+        memory.files.insert(String::from("b.bytes"), vec![0; 8]);
+        memory.files.insert(String::from("Video/a.usm"), vec![1; 4]);
+        memory
+            .files
+            .insert(String::from("Video/VideoV_ab.bytes"), vec![2; 2]);
+        // named by a digest its bytes don't have
+        memory
+            .files
+            .insert(format!("{}.bytes", "0".repeat(32)), vec![0; 4]);
+
+        let source = CachedSource::new(Box::new(memory), BlockCache::new(&dir).unwrap(), false);
+        source.read("M_DesignV.bytes").unwrap();
+        source.read_block("a.bytes", Some(4)).unwrap();
+        source.read_block("b.bytes", Some(8)).unwrap();
+        let corrupt = format!("{}.bytes", "0".repeat(32));
+        source.read_block(&corrupt, Some(4)).unwrap();
+        // named by path, cached by digest
+        let digest = block_digest(&[1; 4]);
+        source.read_digest("Video/a.usm", &digest, Some(4)).unwrap();
+        source.read_block("Video/VideoV_ab.bytes", None).unwrap();
+        assert!(dir.join("blocks/VideoV_ab.bytes").is_file());
+
+        let offline = CachedSource::new(
+            Box::new(MemorySource::default()),
+            BlockCache::new(&dir).unwrap(),
+            true,
+        );
+        // MemorySource describes itself the same, so the mini asset is shared
+        assert_eq!(offline.read("M_DesignV.bytes").unwrap(), b"mini");
+        assert_eq!(offline.read_block("a.bytes", Some(4)).unwrap(), [0; 4]);
+        assert!(offline.read_block("a.bytes", Some(5)).is_err());
+        assert!(offline.read_block(&corrupt, Some(4)).is_err());
+        assert_eq!(
+            offline
+                .read_digest("Video/a.usm", &digest, Some(4))
+                .unwrap(),
+            [1; 4]
+        );
+        assert!(offline.read_digest("Video/a.usm", "../a", None).is_err());
+
+        // a.bytes was just dropped for its size, b.bytes, a.usm and the index
+        // still fit
+        let cache = BlockCache::new(&dir).unwrap();
+        assert_eq!(cache.prune(14).unwrap(), 0);
+        assert_eq!(cache.prune(0).unwrap(), 14);
+        assert!(offline.read_block("b.bytes", None).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,124 +1,402 @@
-use super::hash;
+use super::{hash, source::AssetSource};
 use anyhow::{Context, Result};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use reqwest::{StatusCode, blocking::Client};
 use std::{
     collections::HashMap,
-    io::{Cursor, Read as _},
-    path::PathBuf,
+    io::Cursor,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Mutex,
     thread,
-    time::Duration,
 };
 use tg_asset_meta::{
+    asset_index::{AssetEntry, AssetIndex, IndexKind},
     design_index::{DesignIndex, FileEntry},
+    integrity::{self, BlockError, block_digest},
     mini_asset::MiniAsset,
 };
 use tg_bytes_util::FromBytes;
 
-const MAX_RETRIES: usize = 3;
-const RETRY_DELAY: Duration = Duration::from_millis(100);
-
 pub fn download_all_design_data(
-    design_data_url: String,
+    source: &dyn AssetSource,
     output_folder: Option<PathBuf>,
     filter_hashes: Vec<i32>,
 ) -> Result<HashMap<i32, Vec<u8>>> {
-    let client = Client::new();
-    let mp = MultiProgress::new();
+    Ok(download_layered_design_data(&[source], output_folder, filter_hashes)?.assets)
+}
+
+/// Assets merged from several design data layers, e.g. the StreamingAssets
+/// base data with Persistent hotfixes on top.
+#[derive(Default)]
+pub struct LayeredDesignData {
+    pub assets: HashMap<i32, Vec<u8>>,
+    /// Layer each asset was taken from, as an index into the layers.
+    pub origins: HashMap<i32, usize>,
+    /// Blocks that couldn't be read, whose assets are missing.
+    pub failed: Vec<FailedBlock>,
+}
+
+impl LayeredDesignData {
+    /// Fails unless every block was read.
+    pub fn ensure_complete(&self) -> Result<()> {
+        if let Some(first) = self.failed.first() {
+            anyhow::bail!(
+                "{} blocks failed, the first {}: {:#}",
+                self.failed.len(),
+                first.block,
+                first.error
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A block that couldn't be downloaded or didn't match its index entry, in
+/// which case `error` downcasts to a [`BlockError`].
+#[derive(Debug)]
+pub struct FailedBlock {
+    pub block: String,
+    pub error: anyhow::Error,
+}
+
+/// Reads every layer's own design index and takes each asset from the last
+/// layer that has it. Layers after the first are saved into
+/// `Overlay<N>/DesignData` of the output folder.
+pub fn download_layered_design_data(
+    layers: &[&dyn AssetSource],
+    output_folder: Option<PathBuf>,
+    filter_hashes: Vec<i32>,
+) -> Result<LayeredDesignData> {
+    let layer_folders = (0..layers.len())
+        .map(|layer| {
+            output_folder.as_ref().map(|folder| match layer {
+                0 => folder.clone(),
+                _ => folder.join(format!("Overlay{layer}")),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut indices = Vec::with_capacity(layers.len());
+    for (source, output_folder) in layers.iter().zip(&layer_folders) {
+        indices.push(fetch_design_index(*source, output_folder.as_deref())?);
+    }
+
+    let layers = layers
+        .iter()
+        .zip(&indices)
+        .zip(&layer_folders)
+        .map(|((source, design_index), output_folder)| {
+            (*source, design_index, output_folder.as_deref())
+        })
+        .collect::<Vec<_>>();
+
+    Ok(download_design_files(&layers, &filter_hashes))
+}
 
-    let mini_asset = download_mini_asset(&client, &design_data_url, &mp, &output_folder)
-        .context("Failed to download mini asset")?;
+/// Reads `M_DesignV.bytes` and the design index it names, saving both into
+/// the `DesignData` folder of `output_folder` when given.
+pub fn fetch_design_index(
+    source: &dyn AssetSource,
+    output_folder: Option<&Path>,
+) -> Result<DesignIndex> {
+    tracing::info!("Reading design data from {}", source.describe());
+
+    let mini_asset =
+        download_mini_asset(source, output_folder).context("Failed to download mini asset")?;
 
-    let design_index =
-        download_design_index(&client, &design_data_url, &mp, &mini_asset, &output_folder)
-            .context("Failed to download design index")?;
+    download_design_index(source, &mini_asset, output_folder)
+        .context("Failed to download design index")
+}
 
-    let mut handles = HashMap::with_capacity(design_index.file_list.len());
+/// Downloads the files of already read design indices, lowest priority layer
+/// first, that hold any of `filter_hashes`, or every file when it's empty.
+pub fn download_design_files(
+    layers: &[(&dyn AssetSource, &DesignIndex, Option<&Path>)],
+    filter_hashes: &[i32],
+) -> LayeredDesignData {
+    let mut origins = HashMap::new();
+    for (layer, (_, design_index, _)) in layers.iter().enumerate() {
+        for file_entry in &design_index.file_list {
+            if !filter_hashes.is_empty()
+                && !asset_hashes(file_entry)
+                    .chain(file_entry.data_entries.iter().map(|e| e.name_hash))
+                    .any(|name_hash| filter_hashes.contains(&name_hash))
+            {
+                continue;
+            }
 
-    for file_entry in design_index.file_list {
-        let client = client.clone();
-        let design_data_url = design_data_url.clone();
-        let mp = mp.clone();
-        let output_folder = output_folder.clone();
-        let byte_name = file_entry.file_byte_name.clone();
+            for name_hash in asset_hashes(file_entry) {
+                origins.insert(name_hash, layer);
+            }
+        }
+    }
 
-        if !filter_hashes.is_empty()
-            && !file_entry
-                .data_entries
+    // one queue per layer, each drained by as many workers as its source allows
+    let queues = layers
+        .iter()
+        .enumerate()
+        .map(|(layer, (_, design_index, _))| {
+            design_index
+                .file_list
                 .iter()
-                .any(|e| filter_hashes.contains(&e.name_hash))
-        {
-            continue;
+                // only files still supplying something after the layers above
+                .filter(|file_entry| {
+                    asset_hashes(file_entry)
+                        .any(|name_hash| origins.get(&name_hash) == Some(&layer))
+                })
+                .collect::<Vec<_>>()
+        })
+        .map(|files| Mutex::new(files.into_iter()))
+        .collect::<Vec<_>>();
+
+    let results: Vec<Result<BlockAssets, FailedBlock>> = thread::scope(|s| {
+        let mut workers = Vec::new();
+
+        for (layer, ((source, _, output_folder), queue)) in layers.iter().zip(&queues).enumerate() {
+            let (source, output_folder) = (*source, *output_folder);
+            let origins = &origins;
+
+            for _ in 0..source.concurrency().min(queue.lock().unwrap().len()) {
+                workers.push(s.spawn(move || {
+                    let mut results = Vec::new();
+                    while let Some(file_entry) = queue.lock().unwrap().next() {
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                            read_file_assets(source, file_entry, output_folder, origins, layer)
+                        }))
+                        .unwrap_or_else(|_| Err(anyhow::format_err!("thread panicked")));
+                        results.push(result.map_err(|error| {
+                            tracing::error!(
+                                "Download error for {}: {:?}",
+                                file_entry.file_byte_name,
+                                error
+                            );
+                            FailedBlock {
+                                block: file_entry.file_byte_name.clone(),
+                                error,
+                            }
+                        }));
+                    }
+                    results
+                }));
+            }
         }
 
-        handles.insert(
-            byte_name,
-            thread::spawn(move || {
-                let data = download_design_bytes(
-                    &client,
-                    &design_data_url,
-                    &mp,
-                    &file_entry,
-                    &output_folder,
-                )?;
-
-                // Special handling for ConfigManifest, since they are in JSON format.
-                if file_entry.name_hash
-                    == hash::get_32bit_hash_const("BakedConfig/ConfigManifest.json")
-                {
-                    return Result::<HashMap<i32, Vec<u8>>>::Ok(HashMap::from([(
-                        file_entry.name_hash,
-                        data,
-                    )]));
-                };
-
-                Ok(file_entry
-                    .data_entries
-                    .iter()
-                    .map(|data_entry| {
-                        let slice = &data[data_entry.offset as usize
-                            ..(data_entry.offset + data_entry.size) as usize];
-
-                        (data_entry.name_hash, slice.to_vec())
-                    })
-                    .collect::<HashMap<i32, Vec<u8>>>())
-            }),
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+
+    // the same call as `verify` makes
+    let read = results.iter().filter(|result| result.is_ok()).count();
+    let mismatched = results
+        .iter()
+        .filter(|result| matches!(result, Ok((_, Err(_)))))
+        .count();
+    let check_digests = integrity::names_are_digests(read, mismatched);
+    if !check_digests && mismatched > 0 {
+        tracing::warn!(
+            "No block name matched the MD5 of its content, names may be another hash, digests not checked"
         );
     }
 
-    let results: HashMap<String, HashMap<i32, Vec<u8>>> = handles
-        .into_iter()
-        .filter_map(|(byte_name, handle)| match handle.join() {
-            Ok(Ok(data)) => Some((byte_name, data)),
-            Ok(Err(e)) => {
-                tracing::error!("Download error: {:?}", e);
-                None
-            }
-            Err(e) => {
-                tracing::error!("Thread panicked: {:?}", e);
-                None
+    let mut assets = HashMap::new();
+    let mut failed = Vec::new();
+    for result in results {
+        match result {
+            Ok((_, Err(err))) if check_digests => {
+                tracing::error!("Download error for {}: {err}", err.block());
+                failed.push(FailedBlock {
+                    block: err.block().to_string(),
+                    error: err.into(),
+                });
             }
+            Ok((data, _)) => assets.extend(data),
+            Err(block) => failed.push(block),
+        }
+    }
+    origins.retain(|name_hash, _| assets.contains_key(name_hash));
+
+    LayeredDesignData {
+        assets,
+        origins,
+        failed,
+    }
+}
+
+/// Assets sliced out of a block, with whether the block has the digest its
+/// name says, which only counts once every block is read.
+type BlockAssets = (HashMap<i32, Vec<u8>>, Result<(), BlockError>);
+
+/// Reads a file and slices out the assets it supplies from `layer`.
+fn read_file_assets(
+    source: &dyn AssetSource,
+    file_entry: &FileEntry,
+    output_folder: Option<&Path>,
+    origins: &HashMap<i32, usize>,
+    layer: usize,
+) -> Result<BlockAssets> {
+    let data = download_design_bytes(source, file_entry, output_folder)?;
+    file_entry.check_size(&data)?;
+    let digest = file_entry.check_digest(&data);
+
+    // Special handling for ConfigManifest, since they are in JSON format.
+    if file_entry.name_hash == hash::get_32bit_hash_const("BakedConfig/ConfigManifest.json") {
+        return Ok((HashMap::from([(file_entry.name_hash, data)]), digest));
+    };
+
+    let assets = file_entry
+        .data_entries
+        .iter()
+        .filter(|data_entry| origins.get(&data_entry.name_hash) == Some(&layer))
+        .map(|data_entry| {
+            let slice = file_entry.entry_data(data_entry, &data)?;
+            Ok((data_entry.name_hash, slice.to_vec()))
         })
-        .collect();
+        .collect::<Result<_, BlockError>>()?;
+    Ok((assets, digest))
+}
+
+/// Name hashes of the assets a file supplies, ConfigManifest is a single
+/// JSON asset under the file's own hash.
+pub fn asset_hashes(file_entry: &FileEntry) -> impl Iterator<Item = i32> + '_ {
+    let is_manifest =
+        file_entry.name_hash == hash::get_32bit_hash_const("BakedConfig/ConfigManifest.json");
+    let entries = if is_manifest {
+        &[][..]
+    } else {
+        &file_entry.data_entries[..]
+    };
 
-    Ok(results
+    is_manifest
+        .then_some(file_entry.name_hash)
         .into_iter()
-        .flat_map(|(_, inner)| inner.into_iter())
-        .collect())
+        .chain(entries.iter().map(|data_entry| data_entry.name_hash))
+}
+
+/// Where an entry's data lives: `(block, offset, size)`. Blocks are named by
+/// their content, so an entry still at the same place of the same block
+/// hasn't changed.
+pub type EntryKey<'a> = (&'a str, u32, u32);
+
+/// The place of every asset of an index, ConfigManifest being the whole block.
+pub fn entry_keys(design_index: &DesignIndex) -> HashMap<i32, EntryKey<'_>> {
+    let manifest_hash = hash::get_32bit_hash_const("BakedConfig/ConfigManifest.json");
+
+    let mut keys = HashMap::new();
+    for file_entry in &design_index.file_list {
+        let block = file_entry.file_byte_name.as_str();
+        if file_entry.name_hash == manifest_hash {
+            keys.insert(manifest_hash, (block, 0, 0));
+            continue;
+        }
+
+        for data_entry in &file_entry.data_entries {
+            keys.insert(
+                data_entry.name_hash,
+                (block, data_entry.offset, data_entry.size),
+            );
+        }
+    }
+    keys
+}
+
+/// Reads the mini asset of another index kind and the index it names, which
+/// is named by its content like a design index.
+pub fn fetch_asset_index(
+    source: &dyn AssetSource,
+    kind: IndexKind,
+) -> Result<(MiniAsset, AssetIndex)> {
+    let name = kind.mini_asset_path();
+    let mini_asset = MiniAsset::from_bytes(&mut Cursor::new(&source.read(&name)?))
+        .context(format!("Failed to parse {name}"))?;
+
+    let name = kind.index_path(&mini_asset.design_index_hash.to_string());
+    let index = AssetIndex::parse(kind, &source.read_block(&name, None)?)
+        .context(format!("Failed to parse {name}"))?;
+
+    Ok((mini_asset, index))
+}
+
+/// Downloads files listed by an index into `output_folder` under their name,
+/// checking their size and MD5. Returns how many failed.
+pub fn download_asset_files(
+    source: &dyn AssetSource,
+    kind: IndexKind,
+    entries: &[&AssetEntry],
+    output_folder: &Path,
+) -> usize {
+    let queue = Mutex::new(entries.iter());
+    let failed = Mutex::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..source.concurrency().min(entries.len()) {
+            s.spawn(|| {
+                while let Some(entry) = queue.lock().unwrap().next() {
+                    if let Err(e) = download_asset_file(source, kind, entry, output_folder) {
+                        tracing::error!("Download error for {}: {:?}", entry.name, e);
+                        *failed.lock().unwrap() += 1;
+                    }
+                }
+            });
+        }
+    });
+
+    failed.into_inner().unwrap()
+}
+
+fn download_asset_file(
+    source: &dyn AssetSource,
+    kind: IndexKind,
+    entry: &AssetEntry,
+    output_folder: &Path,
+) -> Result<()> {
+    // named by path, so only cached by digest
+    let path = kind.path(&entry.name);
+    let data = match &entry.md5 {
+        Some(md5) => source.read_digest(&path, md5, Some(entry.size))?,
+        None => source.read(&path)?,
+    };
+    if data.len() as u64 != entry.size {
+        anyhow::bail!(
+            "{} is {} bytes, expected {}",
+            entry.name,
+            data.len(),
+            entry.size
+        );
+    }
+    if let Some(md5) = &entry.md5 {
+        let actual = block_digest(&data);
+        if !actual.eq_ignore_ascii_case(md5) {
+            anyhow::bail!("{} has MD5 {actual}, expected {md5}", entry.name);
+        }
+    }
+
+    let relative = Path::new(&entry.name);
+    if !relative
+        .components()
+        .all(|component| matches!(component, std::path::Component::Normal(_)))
+    {
+        anyhow::bail!("{} isn't a relative path", entry.name);
+    }
+    let path = output_folder.join(relative);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, data).context(format!("Failed to write {path:?}"))
+}
+
+/// Downloads just `M_DesignV.bytes`, e.g. to pick a schema for its revision
+/// before downloading everything else.
+pub fn fetch_mini_asset(source: &dyn AssetSource) -> Result<MiniAsset> {
+    download_mini_asset(source, None)
 }
 
 #[inline]
 fn download_mini_asset(
-    client: &Client,
-    design_data_url: &str,
-    mp: &MultiProgress,
-    output_folder: &Option<PathBuf>,
+    source: &dyn AssetSource,
+    output_folder: Option<&Path>,
 ) -> anyhow::Result<MiniAsset> {
-    let res = download_bytes(
-        client,
-        &format!("{design_data_url}/client/Windows/M_DesignV.bytes"),
-        mp,
-    )?;
+    let res = source.read("M_DesignV.bytes")?;
     let mini_asset = MiniAsset::from_bytes(&mut Cursor::new(&res))?;
 
     save_file(output_folder, &res, "M_DesignV.bytes");
@@ -128,18 +406,12 @@ fn download_mini_asset(
 
 #[inline]
 fn download_design_index(
-    client: &Client,
-    design_data_url: &str,
-    mp: &MultiProgress,
+    source: &dyn AssetSource,
     mini_asset: &MiniAsset,
-    output_folder: &Option<PathBuf>,
+    output_folder: Option<&Path>,
 ) -> Result<DesignIndex> {
     let name = format!("DesignV_{}.bytes", mini_asset.design_index_hash);
-    let res = download_bytes(
-        client,
-        &format!("{design_data_url}/client/Windows/{name}",),
-        mp,
-    )?;
+    let res = source.read_block(&name, None)?;
 
     let design_index = DesignIndex::from_bytes(&mut Cursor::new(&res))?;
 
@@ -150,25 +422,19 @@ fn download_design_index(
 
 #[inline]
 fn download_design_bytes(
-    client: &Client,
-    design_data_url: &str,
-    mp: &MultiProgress,
+    source: &dyn AssetSource,
     file_entry: &FileEntry,
-    output_folder: &Option<PathBuf>,
+    output_folder: Option<&Path>,
 ) -> Result<Vec<u8>> {
     let name = format!("{}.bytes", file_entry.file_byte_name);
-    let bytes = download_bytes(
-        client,
-        &format!("{design_data_url}/client/Windows/{name}",),
-        mp,
-    )?;
+    let bytes = source.read_block(&name, Some(file_entry.size as u64))?;
     save_file(output_folder, &bytes, &name);
 
     Ok(bytes)
 }
 
 #[inline]
-fn save_file(output_folder: &Option<PathBuf>, bytes: &Vec<u8>, file_name: &str) {
+fn save_file(output_folder: Option<&Path>, bytes: &[u8], file_name: &str) {
     if let Some(output_folder) = output_folder {
         let output_folder = output_folder.join("DesignData");
         if !output_folder.is_dir() {
@@ -178,53 +444,121 @@ fn save_file(output_folder: &Option<PathBuf>, bytes: &Vec<u8>, file_name: &str)
     }
 }
 
-fn download_bytes(client: &Client, design_data_url: &str, mp: &MultiProgress) -> Result<Vec<u8>> {
-    if !design_data_url.starts_with("http") {
-        return Ok(std::fs::read(
-            design_data_url.replace("client/Windows", ""),
-        )?);
-    }
+#[cfg(test)]
+mod tests {
+    use super::download_layered_design_data;
+    use crate::source::MemorySource;
+    use std::collections::HashMap;
+    use tg_asset_meta::{
+        integrity::{BlockError, block_digest},
+        mini_asset::MiniAsset,
+        repack::{RepackOptions, repack},
+    };
+    use tg_bytes_util::ByteHash16;
 
-    for attempt in 1..=MAX_RETRIES {
-        let result = (|| -> Result<Vec<u8>> {
-            let resp = client.get(design_data_url).send()?;
-            let status = resp.status();
-            if status != StatusCode::OK {
-                return Err(anyhow::format_err!(
-                    "Server returned non OK code for {design_data_url} {:?}",
-                    status
-                ));
-            }
+    /// A layer with one file holding `entries` as (name hash, data).
+    fn layer(index_hash: u8, entries: &[(i32, &[u8])]) -> MemorySource {
+        let mini_asset = MiniAsset::new(1, ByteHash16::new([index_hash; 16]), 0);
+        let mut mini = Vec::new();
+        mini_asset.write(&mut mini).unwrap();
 
-            let total = resp.content_length().unwrap_or(0);
-            let pb = mp.add(ProgressBar::new(total));
-            pb.set_style(
-                ProgressStyle::with_template("{msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, ETA: {eta})")?
-                    .progress_chars("##-"),
-            );
+        let data = entries
+            .iter()
+            .flat_map(|(_, bytes)| bytes.iter().copied())
+            .collect::<Vec<_>>();
+        let mut index = Vec::new();
+        index.extend(0i64.to_be_bytes());
+        index.extend(1i32.to_be_bytes());
+        index.extend((entries.len() as i32).to_be_bytes());
+        index.extend(0i32.to_be_bytes());
+        index.extend(md5::compute(&data).0);
+        index.extend((data.len() as i64).to_be_bytes());
+        index.extend((entries.len() as i32).to_be_bytes());
+        let mut offset = 0u32;
+        for (name_hash, bytes) in entries {
+            index.extend(name_hash.to_be_bytes());
+            index.extend((bytes.len() as u32).to_be_bytes());
+            index.extend(offset.to_be_bytes());
+            offset += bytes.len() as u32;
+        }
+        index.push(0);
 
-            let file_name = design_data_url.split('/').next_back().unwrap_or_default();
-            pb.set_message(format!("Downloading {file_name}"));
+        let mut source = MemorySource::default();
+        source.files.insert(String::from("M_DesignV.bytes"), mini);
+        source.files.insert(
+            format!("DesignV_{}.bytes", mini_asset.design_index_hash),
+            index,
+        );
+        source
+            .files
+            .insert(format!("{}.bytes", block_digest(&data)), data);
+        source
+    }
 
-            let mut reader = pb.wrap_read(resp);
-            let mut buffer = Vec::with_capacity(total as usize);
-            reader.read_to_end(&mut buffer)?;
+    #[test]
+    fn test_overlay() {
+        let base = layer(1, &[(1, b"base"), (2, b"base")]);
+        let hotfix = layer(2, &[(2, b"hotfix"), (3, b"hotfix")]);
 
-            pb.finish_with_message(format!("Downloaded {file_name}"));
-            Ok(buffer)
-        })();
+        let merged = download_layered_design_data(&[&base, &hotfix], None, Vec::new()).unwrap();
+        assert_eq!(merged.assets[&1], b"base");
+        assert_eq!(merged.assets[&2], b"hotfix");
+        assert_eq!(merged.assets[&3], b"hotfix");
+        assert_eq!(merged.origins[&1], 0);
+        assert_eq!(merged.origins[&2], 1);
+    }
 
-        match result {
-            Ok(data) => return Ok(data),
-            Err(e) if attempt < MAX_RETRIES => {
-                mp.println(format!(
-                    "Retry {attempt}/{MAX_RETRIES} for {design_data_url} due to error: {e}"
-                ))?;
-                std::thread::sleep(RETRY_DELAY);
-            }
-            Err(e) => return Err(e),
-        }
+    #[test]
+    fn test_failed_blocks() {
+        let mut source = layer(1, &[(1, b"data")]);
+        source
+            .files
+            .remove(&format!("{}.bytes", block_digest(b"data")));
+
+        let data = download_layered_design_data(&[&source], None, Vec::new()).unwrap();
+        assert!(data.assets.is_empty());
+        assert_eq!(data.failed.len(), 1);
+        assert!(data.ensure_complete().is_err());
     }
 
-    unreachable!()
+    #[test]
+    fn test_block_digests() {
+        let assets = HashMap::from([(1, b"aaaa".to_vec()), (2, b"bbbb".to_vec())]);
+        let repacked = repack(
+            &assets,
+            &RepackOptions {
+                max_block_size: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut source = MemorySource {
+            files: repacked.files().unwrap().into_iter().collect(),
+        };
+        let block = |name_hash| {
+            let file_entry = repacked
+                .design_index
+                .file_list
+                .iter()
+                .find(|file_entry| file_entry.name_hash == name_hash)
+                .unwrap();
+            format!("{}.bytes", file_entry.file_byte_name)
+        };
+
+        // one corrupt block among good ones fails
+        source.files.insert(block(2), b"bbbX".to_vec());
+        let data = download_layered_design_data(&[&source], None, Vec::new()).unwrap();
+        assert_eq!(data.assets[&1], b"aaaa");
+        assert!(!data.assets.contains_key(&2));
+        assert!(matches!(
+            data.failed[0].error.downcast_ref(),
+            Some(BlockError::DigestMismatch { .. })
+        ));
+
+        // when no name is the MD5 of its block, names are some other hash
+        source.files.insert(block(1), b"aaaX".to_vec());
+        let data = download_layered_design_data(&[&source], None, Vec::new()).unwrap();
+        assert_eq!(data.assets.len(), 2);
+        assert!(data.failed.is_empty());
+    }
 }
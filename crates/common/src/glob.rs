@@ -0,0 +1,67 @@
+//! Shell-style patterns over asset paths: `?` is any one character, `*` any
+//! run of characters within a folder and `**` any run across folders.
+
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let path = path.as_bytes();
+
+    // positions to retry from when the last star should take one more byte
+    let (mut p, mut s) = (0, 0);
+    let mut star: Option<(usize, usize, bool)> = None;
+    while s < path.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                let deep = pattern.get(p + 1) == Some(&b'*');
+                p += if deep { 2 } else { 1 };
+                star = Some((p, s, deep));
+            }
+            Some(b'?') if path[s] != b'/' => {
+                p += 1;
+                s += 1;
+            }
+            Some(c) if *c != b'?' && *c == path[s] => {
+                p += 1;
+                s += 1;
+            }
+            _ => match star {
+                Some((star_p, star_s, deep)) if deep || path[star_s] != b'/' => {
+                    p = star_p;
+                    s = star_s + 1;
+                    star = Some((star_p, s, deep));
+                }
+                _ => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches(
+            "BakedConfig/ExcelOutput/*.bytes",
+            "BakedConfig/ExcelOutput/AvatarConfig.bytes"
+        ));
+        assert!(!matches(
+            "BakedConfig/*.bytes",
+            "BakedConfig/ExcelOutput/AvatarConfig.bytes"
+        ));
+        assert!(matches(
+            "BakedConfig/**.bytes",
+            "BakedConfig/ExcelOutput/AvatarConfig.bytes"
+        ));
+        assert!(matches(
+            "**/Avatar?onfig.bytes",
+            "BakedConfig/ExcelOutput/AvatarConfig.bytes"
+        ));
+        assert!(matches("*", "Intro.usm"));
+        assert!(!matches("*.usm", "Intro.usm.bak"));
+        assert!(matches("a*b*c", "axxbyyc"));
+        assert!(!matches("a?c", "a/c"));
+    }
+}
@@ -1,3 +1,6 @@
+pub mod cache;
 pub mod downloader;
+pub mod glob;
 pub mod hash;
 pub mod logging;
+pub mod source;
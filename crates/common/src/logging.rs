@@ -18,6 +18,22 @@ pub fn init(level: tracing::Level) {
         .init();
 }
 
+/// [`init`] writing to stderr, for commands whose output goes to stdout.
+pub fn init_stderr(level: tracing::Level) {
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_env_filter(
+            EnvFilter::builder()
+                .with_default_directive(level.into())
+                .from_env()
+                .unwrap(),
+        )
+        .with_writer(std::io::stderr)
+        .without_time()
+        .with_target(false)
+        .init();
+}
+
 pub fn init_info_only() {
     tracing_subscriber::registry()
         .with(
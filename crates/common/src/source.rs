@@ -0,0 +1,778 @@
+//! Where design data files come from. Every file is looked up by its name
+//! under the design data root, e.g. `M_DesignV.bytes` or
+//! `DesignV_<hash>.bytes`.
+
+use anyhow::{Context as _, Result};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::{
+    StatusCode,
+    blocking::Client,
+    header::{CONTENT_RANGE, RANGE},
+};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::{BuildHasher as _, RandomState},
+    io::{BufReader, Read as _},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+pub trait AssetSource: Send + Sync {
+    /// Reads a whole file by its name under the design data root.
+    fn read(&self, name: &str) -> Result<Vec<u8>>;
+
+    /// Reads a block named by its content, i.e. a design index or a
+    /// `FileEntry` file, with its size when the index gives one.
+    fn read_block(&self, name: &str, size: Option<u64>) -> Result<Vec<u8>> {
+        let _ = size;
+        self.read(name)
+    }
+
+    /// Reads a file named by path whose content has the MD5 `digest`, e.g.
+    /// one listed by an asset index. Caches keep it by its digest.
+    fn read_digest(&self, name: &str, digest: &str, size: Option<u64>) -> Result<Vec<u8>> {
+        let _ = digest;
+        self.read_block(name, size)
+    }
+
+    /// Whether reads go over the network.
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    /// How many files to read at once.
+    fn concurrency(&self) -> usize {
+        thread::available_parallelism().map_or(4, NonZeroUsize::get)
+    }
+
+    /// Where files come from, for logs.
+    fn describe(&self) -> String;
+}
+
+/// Client platform of the design data, each has its own folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Platform {
+    #[default]
+    Windows,
+    Android,
+    Ios,
+}
+
+impl Platform {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Platform::Windows => "Windows",
+            Platform::Android => "Android",
+            Platform::Ios => "iOS",
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Picks a backend for a command line source: an `http(s)://` design data
+/// URL, a `.zip`, `.tar`, `.tar.gz` or `.tgz` archive, an output folder of
+/// `--save-bytes-file`, or a Persistent `DesignData/Windows` folder.
+pub fn open(source: &str) -> Result<Box<dyn AssetSource>> {
+    open_with(source, Platform::default(), HttpOptions::default())
+}
+
+/// [`open`] for a platform and with the given options for URLs. Platform
+/// folders are picked from a URL's `client/<platform>`, a `DesignData`
+/// folder's `<platform>` subfolder, or an archive's `<platform>` folder.
+pub fn open_with(
+    source: &str,
+    platform: Platform,
+    http: HttpOptions,
+) -> Result<Box<dyn AssetSource>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        return HttpSource::with_options(source, platform, http)
+            .map(|source| Box::new(source) as _);
+    }
+
+    let path = Path::new(source);
+    let lower = source.to_ascii_lowercase();
+    if path.is_file() {
+        if lower.ends_with(".zip") {
+            return Ok(Box::new(ArchiveSource::open_zip(path, platform)?));
+        } else if [".tar", ".tar.gz", ".tgz"]
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+        {
+            return Ok(Box::new(ArchiveSource::open_tar(path, platform)?));
+        }
+        anyhow::bail!("unsupported archive {source}, expected .zip, .tar, .tar.gz or .tgz");
+    }
+
+    if path.join("DesignData").is_dir() {
+        Ok(Box::new(DirectorySource::saved_bytes(path)))
+    } else if path.join(platform.as_str()).is_dir() {
+        Ok(Box::new(DirectorySource::persistent(
+            &path.join(platform.as_str()),
+        )))
+    } else if path.is_dir() {
+        Ok(Box::new(DirectorySource::persistent(path)))
+    } else {
+        anyhow::bail!("{source} is neither a URL, an archive nor a directory")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Files downloaded at once.
+    pub concurrency: usize,
+    /// Failed attempts in a row before giving up on a file. Attempts that
+    /// got some bytes before failing don't count, the next one resumes.
+    pub max_retries: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub connect_timeout: Duration,
+    /// Waiting for the response, then for each read of its body, so a slow
+    /// but steady download never times out however long it takes.
+    pub read_timeout: Duration,
+    /// Bytes per second over all downloads.
+    pub max_bandwidth: Option<u64>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            connect_timeout: Duration::from_secs(30),
+            read_timeout: Duration::from_secs(60),
+            max_bandwidth: None,
+        }
+    }
+}
+
+/// Design data URL, e.g. `https://.../design_data/BetaLive/output_..._...`.
+pub struct HttpSource {
+    base_url: String,
+    platform: Platform,
+    client: Client,
+    mp: MultiProgress,
+    options: HttpOptions,
+    throttle: Option<Throttle>,
+}
+
+impl HttpSource {
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_options(base_url, Platform::default(), HttpOptions::default())
+    }
+
+    pub fn with_options(base_url: &str, platform: Platform, options: HttpOptions) -> Result<Self> {
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            platform,
+            // the blocking client times each read on its own rather than the
+            // whole body, which --max-bandwidth could drag out indefinitely
+            client: Client::builder()
+                .connect_timeout(options.connect_timeout)
+                .timeout(options.read_timeout)
+                .build()?,
+            mp: MultiProgress::new(),
+            throttle: options.max_bandwidth.map(Throttle::new),
+            options,
+        })
+    }
+
+    fn url(&self, name: &str) -> String {
+        format!("{}/client/{}/{name}", self.base_url, self.platform)
+    }
+
+    fn download(&self, name: &str, size: Option<u64>) -> Result<Vec<u8>> {
+        let url = self.url(name);
+
+        let pb = self.mp.add(ProgressBar::new(size.unwrap_or(0)));
+        pb.set_style(
+            ProgressStyle::with_template("{msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, ETA: {eta})")?
+                .progress_chars("##-"),
+        );
+        pb.set_message(format!("Downloading {name}"));
+
+        let mut buffer = Vec::with_capacity(size.unwrap_or(0) as usize);
+        let mut failures = 0;
+        loop {
+            let start = buffer.len();
+            let Err(e) = self.attempt(&url, &mut buffer, &pb) else {
+                pb.finish_with_message(format!("Downloaded {name}"));
+                return Ok(buffer);
+            };
+
+            failures = if buffer.len() > start {
+                1
+            } else {
+                failures + 1
+            };
+            if failures > self.options.max_retries {
+                pb.abandon_with_message(format!("Failed {name}"));
+                return Err(e.context(format!("Giving up on {url}")));
+            }
+
+            let delay = self.backoff(failures);
+            self.mp.println(format!(
+                "Retry {failures}/{} for {url} in {delay:?}{} due to error: {e}",
+                self.options.max_retries,
+                match buffer.len() {
+                    0 => String::new(),
+                    len => format!(" from byte {len}"),
+                }
+            ))?;
+            thread::sleep(delay);
+        }
+    }
+
+    /// One request for the rest of the file, appending to `buffer`.
+    fn attempt(&self, url: &str, buffer: &mut Vec<u8>, pb: &ProgressBar) -> Result<()> {
+        let mut request = self.client.get(url);
+        if !buffer.is_empty() {
+            request = request.header(RANGE, format!("bytes={}-", buffer.len()));
+        }
+
+        let mut resp = request.send()?;
+        match resp.status() {
+            StatusCode::PARTIAL_CONTENT if !buffer.is_empty() => {
+                let start = resp
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|range| range_start(range.to_str().ok()?));
+                if start != Some(buffer.len() as u64) {
+                    // appending would corrupt the file, start over
+                    let resumed = buffer.len();
+                    buffer.clear();
+                    pb.set_position(0);
+                    return Err(anyhow::format_err!(
+                        "Server answered the range from byte {resumed} of {url} with {:?}",
+                        resp.headers().get(CONTENT_RANGE)
+                    ));
+                }
+            }
+            StatusCode::OK => {
+                // the server ignored the range, start over
+                buffer.clear();
+                pb.set_position(0);
+            }
+            status => {
+                return Err(anyhow::format_err!(
+                    "Server returned non OK code for {url} {:?}",
+                    status
+                ));
+            }
+        }
+
+        let expected = resp.content_length().map(|len| buffer.len() as u64 + len);
+        if let Some(expected) = expected {
+            pb.set_length(expected);
+        }
+
+        let mut chunk = vec![0; 64 * 1024];
+        loop {
+            let n = resp.read(&mut chunk)?;
+            if n == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+            pb.inc(n as u64);
+            if let Some(throttle) = &self.throttle {
+                throttle.consume(n as u64);
+            }
+        }
+
+        match expected {
+            Some(expected) if (buffer.len() as u64) < expected => Err(anyhow::format_err!(
+                "Connection closed after {} of {expected} bytes",
+                buffer.len()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Exponential backoff, half of it random so failed downloads don't
+    /// retry in lockstep.
+    fn backoff(&self, failures: usize) -> Duration {
+        let ceiling = self
+            .options
+            .initial_backoff
+            .saturating_mul(1 << (failures - 1).min(16))
+            .min(self.options.max_backoff);
+        let random = RandomState::new().hash_one(failures) % 1000;
+        ceiling / 2 + ceiling.mul_f64(random as f64 / 2000.0)
+    }
+}
+
+/// First byte of a `Content-Range: bytes <first>-<last>/<length>` value.
+#[inline]
+fn range_start(content_range: &str) -> Option<u64> {
+    let (first, _) = content_range.strip_prefix("bytes ")?.split_once('-')?;
+    first.trim().parse().ok()
+}
+
+impl AssetSource for HttpSource {
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        self.download(name, None)
+    }
+
+    fn read_block(&self, name: &str, size: Option<u64>) -> Result<Vec<u8>> {
+        self.download(name, size)
+    }
+
+    fn describe(&self) -> String {
+        format!("{}/client/{}", self.base_url, self.platform)
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    fn concurrency(&self) -> usize {
+        self.options.concurrency.max(1)
+    }
+}
+
+/// Token bucket shared by every download of a source. Going over lets the
+/// bucket go negative, and the reader sleeps until it's paid back.
+struct Throttle {
+    rate: u64,
+    /// Tokens available, and when that was last updated.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl Throttle {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate.max(1),
+            state: Mutex::new((0.0, Instant::now())),
+        }
+    }
+
+    fn consume(&self, bytes: u64) {
+        let rate = self.rate as f64;
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let (tokens, updated) = &mut *state;
+            let now = Instant::now();
+            // at most a second's worth of burst
+            *tokens = (*tokens + now.duration_since(*updated).as_secs_f64() * rate).min(rate);
+            *tokens -= bytes as f64;
+            *updated = now;
+            (*tokens < 0.0).then(|| Duration::from_secs_f64(-*tokens / rate))
+        };
+
+        if let Some(wait) = wait {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// A folder holding every file flat, like the game's Persistent
+/// `DesignData/Windows` folder.
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn persistent(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// An output folder of `--save-bytes-file`, which keeps the files in
+    /// its `DesignData` folder.
+    pub fn saved_bytes(output_dir: &Path) -> Self {
+        Self {
+            root: output_dir.join("DesignData"),
+        }
+    }
+}
+
+impl AssetSource for DirectorySource {
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let path = self.root.join(name);
+        fs::read(&path).context(format!("Failed to read {path:?}"))
+    }
+
+    fn describe(&self) -> String {
+        self.root.display().to_string()
+    }
+}
+
+/// A zip or tar archive of a design data folder. Files are looked up by their
+/// name alone, whatever folder they're in within the archive. Of files with
+/// the same name, the one in the platform's folder is used.
+pub struct ArchiveSource {
+    path: PathBuf,
+    archive: Archive,
+}
+
+enum Archive {
+    Zip {
+        zip: Mutex<zip::ZipArchive<BufReader<File>>>,
+        /// Entry index by file name.
+        entries: HashMap<String, usize>,
+    },
+    /// Tar can't be read out of order, so its files are loaded up front.
+    Tar(HashMap<String, Vec<u8>>),
+}
+
+impl ArchiveSource {
+    pub fn open_zip(path: &Path, platform: Platform) -> Result<Self> {
+        let zip = zip::ZipArchive::new(BufReader::new(File::open(path)?))
+            .context(format!("Failed to open {path:?}"))?;
+        let entries = index_by_name(
+            (0..zip.len()).filter_map(|i| Some((zip.name_for_index(i)?.to_string(), i))),
+            platform,
+        );
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            archive: Archive::Zip {
+                zip: Mutex::new(zip),
+                entries,
+            },
+        })
+    }
+
+    pub fn open_tar(path: &Path, platform: Platform) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let lower = path.to_string_lossy().to_ascii_lowercase();
+        let files = if lower.ends_with(".gz") || lower.ends_with(".tgz") {
+            read_tar(flate2::read::GzDecoder::new(file))
+        } else {
+            read_tar(file)
+        }
+        .context(format!("Failed to read {path:?}"))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            archive: Archive::Tar(index_by_name(files.into_iter(), platform)),
+        })
+    }
+}
+
+fn read_tar(reader: impl std::io::Read) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    for entry in tar::Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().to_string();
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        files.push((path, data));
+    }
+    Ok(files)
+}
+
+/// Keys archive entries by file name, preferring the platform's folder.
+fn index_by_name<T>(
+    entries: impl Iterator<Item = (String, T)>,
+    platform: Platform,
+) -> HashMap<String, T> {
+    let in_platform = |path: &str| {
+        path.split(['/', '\\'])
+            .any(|folder| folder == platform.as_str())
+    };
+
+    let mut by_name: HashMap<String, (bool, T)> = HashMap::new();
+    for (path, value) in entries {
+        let Some(name) = file_name(&path) else {
+            continue;
+        };
+        let preferred = in_platform(&path);
+        match by_name.get(name) {
+            Some((true, _)) => {}
+            Some((false, _)) if !preferred => {}
+            _ => {
+                by_name.insert(name.to_string(), (preferred, value));
+            }
+        }
+    }
+
+    by_name
+        .into_iter()
+        .map(|(name, (_, value))| (name, value))
+        .collect()
+}
+
+#[inline]
+fn file_name(path: &str) -> Option<&str> {
+    path.rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty())
+}
+
+impl AssetSource for ArchiveSource {
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        // files are indexed by name alone, whatever folder is asked for
+        let name = file_name(name).unwrap_or(name);
+        match &self.archive {
+            Archive::Zip { zip, entries } => {
+                let index = *entries
+                    .get(name)
+                    .context(format!("{name} not found in {:?}", self.path))?;
+                let mut zip = zip.lock().unwrap();
+                let mut entry = zip.by_index(index)?;
+                let mut data = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut data)?;
+                Ok(data)
+            }
+            Archive::Tar(files) => files
+                .get(name)
+                .cloned()
+                .context(format!("{name} not found in {:?}", self.path)),
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// Files held in memory, for tests.
+#[derive(Default)]
+pub struct MemorySource {
+    pub files: HashMap<String, Vec<u8>>,
+}
+
+impl AssetSource for MemorySource {
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        self.files
+            .get(name)
+            .cloned()
+            .context(format!("{name} not found in memory"))
+    }
+
+    fn describe(&self) -> String {
+        String::from("memory")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ArchiveSource, AssetSource, HttpOptions, HttpSource, MemorySource, Platform, Throttle,
+        open, open_with,
+    };
+    use std::{
+        collections::HashMap,
+        fs,
+        io::{Read as _, Write as _},
+        net::TcpListener,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    /// Answers one connection per response, in order, and returns the
+    /// request heads it got.
+    fn serve(responses: Vec<&'static [u8]>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut head = Vec::new();
+                let mut byte = [0];
+                while !head.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                    head.push(byte[0]);
+                }
+                requests.push(String::from_utf8(head).unwrap().to_ascii_lowercase());
+                stream.write_all(response).unwrap();
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn test_http_retry_and_resume() {
+        let (url, server) = serve(vec![
+            b"HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            // drops the connection 4 bytes into the body
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\nconnection: close\r\n\r\n0123",
+            b"HTTP/1.1 206 Partial Content\r\ncontent-range: bytes 4-9/10\r\ncontent-length: 6\r\nconnection: close\r\n\r\n456789",
+        ]);
+
+        let source = HttpSource::with_options(
+            &url,
+            Platform::Windows,
+            HttpOptions {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            source.read_block("a.bytes", Some(10)).unwrap(),
+            b"0123456789"
+        );
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("get /client/windows/a.bytes "));
+        assert!(!requests[1].contains("range:"));
+        assert!(requests[2].contains("range: bytes=4-"));
+    }
+
+    #[test]
+    fn test_http_wrong_range() {
+        let (url, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\nconnection: close\r\n\r\n0123",
+            // the rest, but from the wrong place
+            b"HTTP/1.1 206 Partial Content\r\ncontent-range: bytes 2-9/10\r\ncontent-length: 8\r\nconnection: close\r\n\r\n23456789",
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\nconnection: close\r\n\r\n0123456789",
+        ]);
+
+        let source = HttpSource::with_options(
+            &url,
+            Platform::Windows,
+            HttpOptions {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            source.read_block("a.bytes", Some(10)).unwrap(),
+            b"0123456789"
+        );
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].contains("range: bytes=4-"));
+        assert!(!requests[2].contains("range:"));
+    }
+
+    #[test]
+    fn test_http_slow_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = Vec::new();
+            let mut byte = [0];
+            while !head.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                head.push(byte[0]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\n")
+                .unwrap();
+            // longer in total than the timeout, never idle for as long
+            for byte in b"01234" {
+                thread::sleep(Duration::from_millis(150));
+                stream.write_all(&[*byte]).unwrap();
+                stream.flush().unwrap();
+            }
+        });
+
+        let source = HttpSource::with_options(
+            &url,
+            Platform::Windows,
+            HttpOptions {
+                max_retries: 0,
+                read_timeout: Duration::from_millis(500),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(source.read_block("a.bytes", Some(5)).unwrap(), b"01234");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_http_gives_up() {
+        const NOT_FOUND: &[u8] =
+            b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+        let (url, server) = serve(vec![NOT_FOUND, NOT_FOUND]);
+
+        let source = HttpSource::with_options(
+            &url,
+            Platform::Windows,
+            HttpOptions {
+                max_retries: 1,
+                initial_backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(source.read("M_DesignV.bytes").is_err());
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_throttle() {
+        let throttle = Throttle::new(10_000);
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle.consume(1_000);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_sources() {
+        let dir = std::env::temp_dir().join(format!("tg-source-test-{}", std::process::id()));
+        let design_data = dir.join("output").join("DesignData");
+        fs::create_dir_all(&design_data).unwrap();
+        fs::write(design_data.join("M_DesignV.bytes"), b"mini").unwrap();
+
+        // saved bytes layout is picked from the DesignData folder inside
+        let source = open(dir.join("output").to_str().unwrap()).unwrap();
+        assert_eq!(source.read("M_DesignV.bytes").unwrap(), b"mini");
+        let source = open(design_data.to_str().unwrap()).unwrap();
+        assert_eq!(source.read("M_DesignV.bytes").unwrap(), b"mini");
+
+        // a DesignData folder holding every platform
+        let android = dir.join("Persistent").join("Android");
+        fs::create_dir_all(&android).unwrap();
+        fs::write(android.join("M_DesignV.bytes"), b"android").unwrap();
+        let source = open_with(
+            dir.join("Persistent").to_str().unwrap(),
+            Platform::Android,
+            HttpOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(source.read("M_DesignV.bytes").unwrap(), b"android");
+
+        let tar_path = dir.join("design.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&tar_path).unwrap());
+        builder
+            .append_path_with_name(
+                design_data.join("M_DesignV.bytes"),
+                "Windows/M_DesignV.bytes",
+            )
+            .unwrap();
+        builder
+            .append_path_with_name(android.join("M_DesignV.bytes"), "Android/M_DesignV.bytes")
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+        let source = ArchiveSource::open_tar(&tar_path, Platform::Windows).unwrap();
+        assert_eq!(source.read("M_DesignV.bytes").unwrap(), b"mini");
+        assert!(source.read("DesignV_0.bytes").is_err());
+        let source = ArchiveSource::open_tar(&tar_path, Platform::Android).unwrap();
+        assert_eq!(source.read("M_DesignV.bytes").unwrap(), b"android");
+
+        let source = MemorySource {
+            files: HashMap::from([(String::from("a.bytes"), vec![1])]),
+        };
+        assert_eq!(source.read("a.bytes").unwrap(), [1]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
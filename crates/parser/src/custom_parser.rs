@@ -6,7 +6,7 @@ use std::{
 
 use crate::{DynamicParser, ValueKind};
 use base64::Engine;
-use serde_json::{Map, Number, Value, json};
+use serde_json::{Number, Value, json};
 use varint_rs::VarintReader;
 
 use tg_bytes_util::FromBytes;
@@ -57,7 +57,10 @@ fn dynamic_value_parser<'a>(parser: &mut DynamicParser<'a>) -> anyhow::Result<Va
         ),
         1 => (
             String::from("Float"),
-            Value::Number(Number::from_f64(f32::from_bytes(&mut parser.cursor)? as f64).unwrap()),
+            Value::Number(
+                Number::from_f64(f32::from_bytes(&mut parser.cursor)? as f64)
+                    .ok_or_else(|| anyhow::anyhow!("invalid float"))?,
+            ),
         ),
         2 => (
             String::from("Boolean"),
@@ -114,7 +117,7 @@ fn dynamic_values_parser<'a>(parser: &mut DynamicParser<'a>) -> anyhow::Result<V
         return Err(anyhow::format_err!("attempting to allocate large memory!"));
     }
 
-    let mut floats = Map::with_capacity(length);
+    let mut floats = Vec::with_capacity(length);
 
     for _ in 0..length {
         let key = parser.parse(
@@ -159,11 +162,11 @@ fn dynamic_values_parser<'a>(parser: &mut DynamicParser<'a>) -> anyhow::Result<V
             })
         };
 
-        floats.insert(key.to_string(), value);
+        floats.push((key, value));
     }
 
     Ok(json!({
-        "Floats": floats
+        "Floats": parser.collect_dictionary(floats)
     }))
 }
 
@@ -0,0 +1,738 @@
+//! Generates `data.json` and `excel_paths.json` from an Il2CppDumper `dump.cs`.
+//!
+//! Only what the dump shows is used: declared field order, enum members,
+//! base types and attributes. Anything the dump can't tell (which attribute
+//! carries the Typeindex discriminant, explicit maps, classes without an
+//! ExistFlag) comes from a [`SchemaHints`] file.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Deserialize;
+
+use crate::{DataDefine, DataField, ValueKind};
+
+const EXCEL_OUTPUT: &str = "BakedConfig/ExcelOutput/";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SchemaHints {
+    /// Attribute whose first argument is the Typeindex discriminant of the
+    /// class it's on, e.g. `[TypeIndex(3)]`.
+    pub typeindex_attribute: String,
+    /// Extra discriminants by base type, merged over what the attribute gives.
+    pub typeindex: HashMap<String, BTreeMap<u64, String>>,
+    /// Classes serialized without an ExistFlag.
+    pub skip_existflag_check: Vec<String>,
+    /// Fields left out of a type, by type name.
+    pub ignore_fields: HashMap<String, Vec<String>>,
+    /// Defines replacing whatever was generated for a type.
+    pub types: HashMap<String, DataDefine>,
+}
+
+impl Default for SchemaHints {
+    fn default() -> Self {
+        Self {
+            typeindex_attribute: String::from("TypeIndex"),
+            typeindex: HashMap::new(),
+            skip_existflag_check: Vec::new(),
+            ignore_fields: HashMap::new(),
+            types: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GeneratedSchema {
+    pub types: BTreeMap<String, DataDefine>,
+    /// Candidate paths for every Excel row type, most likely first. Paths
+    /// that don't exist in the index are skipped when parsing.
+    pub excel_paths: BTreeMap<String, Vec<String>>,
+    /// Field types that matched no type in the dump, kept as written.
+    pub unresolved: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeclKind {
+    Class,
+    Struct,
+    Enum,
+    Interface,
+}
+
+#[derive(Debug)]
+struct Decl {
+    namespace: String,
+    name: String,
+    kind: DeclKind,
+    bases: Vec<String>,
+    attributes: Vec<String>,
+    /// Instance fields as (type, name), in declaration order.
+    fields: Vec<(String, String)>,
+    /// Enum members as (name, value).
+    members: Vec<(String, String)>,
+    /// Backing type of an enum, from its `value__` field.
+    backing: String,
+}
+
+impl Decl {
+    fn full_name(&self) -> String {
+        if self.namespace.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.namespace, self.name)
+        }
+    }
+
+    fn attribute_arg(&self, attribute: &str) -> Option<&str> {
+        self.attributes.iter().find_map(|attr| {
+            let (name, args) = attr.split_once('(')?;
+            (name == attribute).then(|| {
+                args.trim_end_matches(')')
+                    .split(',')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+            })
+        })
+    }
+}
+
+pub fn generate_schema(dump: &str, hints: &SchemaHints) -> GeneratedSchema {
+    let decls = parse_decls(dump);
+    let resolver = Resolver::new(&decls);
+    let mut out = GeneratedSchema::default();
+
+    let by_name = decls
+        .iter()
+        .map(|decl| (decl.full_name(), decl))
+        .collect::<HashMap<_, _>>();
+
+    for decl in &decls {
+        let full_name = decl.full_name();
+        let define = match decl.kind {
+            DeclKind::Interface => continue,
+            DeclKind::Enum => {
+                let members = decl
+                    .members
+                    .iter()
+                    .map(|(name, value)| (value.clone(), name.clone()))
+                    .collect();
+                let backing = decl.backing.clone();
+                if has_attribute(&decl.attributes, "Flags") {
+                    DataDefine::FlagsEnum(backing, members)
+                } else {
+                    DataDefine::Enum(backing, members)
+                }
+            }
+            DeclKind::Class | DeclKind::Struct => {
+                let ignored = hints.ignore_fields.get(&full_name);
+                let fields = inherited_fields(decl, &by_name, &resolver)
+                    .into_iter()
+                    .filter(|(_, name)| !ignored.is_some_and(|ignored| ignored.contains(name)))
+                    .map(|(type_name, field_name)| DataField {
+                        field_name,
+                        data_type: resolver.kind(&type_name, &decl.namespace, &mut out.unresolved),
+                    })
+                    .collect();
+                let interfaces = decl
+                    .bases
+                    .iter()
+                    .filter_map(|base| resolver.resolve(base, &decl.namespace))
+                    .filter(|base| {
+                        by_name
+                            .get(base)
+                            .is_some_and(|base| base.kind == DeclKind::Interface)
+                    })
+                    .collect();
+
+                if decl.kind == DeclKind::Struct {
+                    DataDefine::Struct { fields, interfaces }
+                } else {
+                    DataDefine::Class {
+                        skip_existflag_check: hints
+                            .skip_existflag_check
+                            .contains(&full_name)
+                            .then_some(true),
+                        fields,
+                        interfaces,
+                    }
+                }
+            }
+        };
+
+        if is_excel_row(decl) {
+            out.excel_paths
+                .insert(full_name.clone(), excel_path_candidates(&full_name));
+        }
+        out.types.insert(full_name, define);
+    }
+
+    for (base, descendants) in typeindex_maps(&decls, &by_name, &resolver, hints) {
+        out.types.insert(
+            base.clone(),
+            DataDefine::Typeindex {
+                base,
+                descendants: descendants
+                    .into_iter()
+                    .map(|(typeindex, name)| (typeindex, ValueKind::Class(name)))
+                    .collect(),
+            },
+        );
+    }
+
+    for (name, define) in &hints.types {
+        out.types.insert(name.clone(), define.clone());
+    }
+
+    out
+}
+
+/// Paths an Excel row type is likely stored under, most likely first: the
+/// type name without its namespace and `Row` suffix, then also without a
+/// trailing `Excel` or `Config`, or any `Excel` at all.
+pub fn excel_path_candidates(type_name: &str) -> Vec<String> {
+    let short = type_name.rsplit('.').next().unwrap_or(type_name);
+    let stem = short.strip_suffix("Row").unwrap_or(short);
+
+    let mut stems = vec![stem.to_string()];
+    for excel in ["Excel", "Config"] {
+        if let Some(trimmed) = stem.strip_suffix(excel) {
+            stems.push(trimmed.to_string());
+        }
+    }
+    if stem.contains("Excel") {
+        stems.push(stem.replace("Excel", ""));
+    }
+
+    let mut paths = Vec::with_capacity(stems.len());
+    for stem in stems {
+        let path = format!("{EXCEL_OUTPUT}{stem}.bytes");
+        if !stem.is_empty() && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+fn is_excel_row(decl: &Decl) -> bool {
+    decl.kind == DeclKind::Class
+        && decl.name.ends_with("Row")
+        && (decl.name.contains("Excel") || decl.bases.iter().any(|base| base.contains("Excel")))
+}
+
+/// Fields of every base class first, as they're serialized.
+fn inherited_fields(
+    decl: &Decl,
+    by_name: &HashMap<String, &Decl>,
+    resolver: &Resolver,
+) -> Vec<(String, String)> {
+    let mut chain = vec![decl];
+    let mut current = decl;
+    while let Some(base) = current
+        .bases
+        .first()
+        .and_then(|base| resolver.resolve(base, &current.namespace))
+        .and_then(|base| by_name.get(&base))
+        .filter(|base| base.kind == DeclKind::Class && chain.len() < 32)
+    {
+        chain.push(base);
+        current = base;
+    }
+
+    chain
+        .into_iter()
+        .rev()
+        .flat_map(|decl| decl.fields.iter().cloned())
+        .collect()
+}
+
+/// Discriminants by base type, from the hinted attribute on every class and
+/// registered under each of its base classes and interfaces.
+fn typeindex_maps(
+    decls: &[Decl],
+    by_name: &HashMap<String, &Decl>,
+    resolver: &Resolver,
+    hints: &SchemaHints,
+) -> BTreeMap<String, BTreeMap<u64, String>> {
+    let mut maps: BTreeMap<String, BTreeMap<u64, String>> = BTreeMap::new();
+    let attribute = normalize_attribute(&hints.typeindex_attribute);
+
+    for decl in decls {
+        let Some(typeindex) = decl
+            .attribute_arg(&attribute)
+            .and_then(|arg| arg.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        let full_name = decl.full_name();
+
+        let mut pending = decl
+            .bases
+            .iter()
+            .map(|base| (base, &decl.namespace))
+            .collect::<Vec<_>>();
+        let mut seen = BTreeSet::new();
+        while let Some((base, namespace)) = pending.pop() {
+            let Some(base) = resolver.resolve(base, namespace) else {
+                continue;
+            };
+            if base == full_name || !seen.insert(base.clone()) {
+                continue;
+            }
+            maps.entry(base.clone())
+                .or_default()
+                .insert(typeindex, full_name.clone());
+            if let Some(base) = by_name.get(&base) {
+                pending.extend(base.bases.iter().map(|next| (next, &base.namespace)));
+            }
+        }
+    }
+
+    for (base, descendants) in &hints.typeindex {
+        maps.entry(base.clone())
+            .or_default()
+            .extend(descendants.iter().map(|(k, v)| (*k, v.clone())));
+    }
+
+    maps
+}
+
+struct Resolver {
+    /// Full names by the short name fields refer to them with.
+    by_short_name: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Resolver {
+    fn new(decls: &[Decl]) -> Self {
+        let mut by_short_name: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        for decl in decls {
+            by_short_name
+                .entry(decl.name.clone())
+                .or_default()
+                .push((decl.namespace.clone(), decl.full_name()));
+        }
+        for candidates in by_short_name.values_mut() {
+            candidates.sort();
+        }
+        Self { by_short_name }
+    }
+
+    /// Full name of a type as written in `namespace`, preferring a type in
+    /// that namespace when the short name is ambiguous.
+    fn resolve(&self, name: &str, namespace: &str) -> Option<String> {
+        if let Some(candidates) = self.by_short_name.get(name) {
+            return candidates
+                .iter()
+                .find(|(ns, _)| ns == namespace)
+                .or_else(|| candidates.first())
+                .map(|(_, full_name)| full_name.clone());
+        }
+
+        // already qualified
+        let short = name.rsplit('.').next()?;
+        self.by_short_name
+            .get(short)?
+            .iter()
+            .find(|(_, full_name)| full_name == name)
+            .map(|(_, full_name)| full_name.clone())
+    }
+
+    fn kind(
+        &self,
+        type_name: &str,
+        namespace: &str,
+        unresolved: &mut BTreeSet<String>,
+    ) -> ValueKind {
+        let type_name = type_name.trim();
+        let type_name = type_name
+            .strip_prefix("System.")
+            .or_else(|| type_name.strip_prefix("UnityEngine."))
+            .unwrap_or(type_name);
+
+        if let Some(item) = type_name.strip_suffix("[]") {
+            return match item.trim() {
+                "byte" => ValueKind::Bytes,
+                item => ValueKind::Array(Box::new(self.kind(item, namespace, unresolved))),
+            };
+        }
+        if let Some(value) = type_name.strip_suffix('?') {
+            return ValueKind::Nullable(Box::new(self.kind(value, namespace, unresolved)));
+        }
+
+        if let Some((generic, args)) = type_name
+            .strip_suffix('>')
+            .and_then(|name| name.split_once('<'))
+        {
+            let args = split_generic_args(args)
+                .into_iter()
+                .map(|arg| self.kind(arg, namespace, unresolved))
+                .collect::<Vec<_>>();
+            let generic = generic.rsplit('.').next().unwrap_or(generic);
+            return match (generic, args.as_slice()) {
+                ("List" | "IList" | "IReadOnlyList" | "IEnumerable", [item]) => {
+                    ValueKind::Array(Box::new(item.clone()))
+                }
+                ("HashSet" | "ISet", [item]) => ValueKind::Set(Box::new(item.clone())),
+                ("Nullable", [value]) => ValueKind::Nullable(Box::new(value.clone())),
+                (
+                    "Dictionary" | "SortedDictionary" | "IDictionary" | "IReadOnlyDictionary",
+                    [key, value],
+                ) => ValueKind::Dictionary(Box::new(key.clone()), Box::new(value.clone())),
+                _ => {
+                    unresolved.insert(type_name.to_string());
+                    ValueKind::Other()
+                }
+            };
+        }
+
+        match type_name {
+            "byte" | "sbyte" | "short" | "ushort" | "int" | "uint" | "long" | "ulong" | "float"
+            | "double" | "bool" | "string" | "char" | "decimal" => {
+                ValueKind::Primitive(type_name.to_string())
+            }
+            "Vector2" => ValueKind::Vector2,
+            "Vector3" => ValueKind::Vector3,
+            "Vector4" => ValueKind::Vector4,
+            "Quaternion" => ValueKind::Quaternion,
+            "Color" => ValueKind::Color,
+            _ => match self.resolve(type_name, namespace) {
+                Some(full_name) => ValueKind::Class(full_name),
+                None => {
+                    unresolved.insert(type_name.to_string());
+                    ValueKind::Class(type_name.to_string())
+                }
+            },
+        }
+    }
+}
+
+fn split_generic_args(args: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                out.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(args[start..].trim());
+    out
+}
+
+const MODIFIERS: [&str; 14] = [
+    "public",
+    "private",
+    "protected",
+    "internal",
+    "static",
+    "readonly",
+    "sealed",
+    "abstract",
+    "volatile",
+    "unsafe",
+    "partial",
+    "new",
+    "override",
+    "ref",
+];
+
+/// Strips the modifiers off a declaration, noting whether it was static or
+/// const.
+fn strip_modifiers(mut line: &str) -> (&str, bool) {
+    let mut is_static = false;
+    loop {
+        let Some((word, rest)) = line.split_once(' ') else {
+            return (line, is_static);
+        };
+        if word == "static" || word == "const" {
+            is_static = true;
+        } else if !MODIFIERS.contains(&word) {
+            return (line, is_static);
+        }
+        line = rest.trim_start();
+    }
+}
+
+fn parse_decls(dump: &str) -> Vec<Decl> {
+    let mut decls = Vec::new();
+    let mut namespace = String::new();
+    let mut attributes = Vec::new();
+    let mut current: Option<Decl> = None;
+    let mut in_fields = false;
+    let mut field_attributes = Vec::new();
+
+    for line in dump.lines() {
+        let line = line.trim();
+
+        if let Some(ns) = line.strip_prefix("// Namespace:") {
+            namespace = ns.trim().to_string();
+            continue;
+        }
+
+        if current.is_some() {
+            match line {
+                "}" => {
+                    decls.extend(current.take());
+                    in_fields = false;
+                }
+                "// Fields" => in_fields = true,
+                _ if line.starts_with("// ") && line.ends_with('s') && !line.contains(':') => {
+                    in_fields = false
+                }
+                _ if in_fields => {
+                    let line = line.split(" // ").next().unwrap_or(line).trim();
+                    let (line_attributes, line) = split_attributes(line);
+                    field_attributes.extend(line_attributes);
+                    if line.is_empty() {
+                        continue;
+                    }
+                    if let Some(decl) = current.as_mut() {
+                        parse_field(decl, line, &field_attributes);
+                    }
+                    field_attributes.clear();
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let line = line.split(" // ").next().unwrap_or(line).trim();
+        let (line_attributes, line) = split_attributes(line);
+        attributes.extend(line_attributes);
+        if line.is_empty() {
+            continue;
+        }
+
+        let (decl_line, _) = strip_modifiers(line);
+        let Some((keyword, rest)) = decl_line.split_once(' ') else {
+            attributes.clear();
+            continue;
+        };
+        let kind = match keyword {
+            "class" => DeclKind::Class,
+            "struct" => DeclKind::Struct,
+            "enum" => DeclKind::Enum,
+            "interface" => DeclKind::Interface,
+            _ => {
+                attributes.clear();
+                continue;
+            }
+        };
+
+        let (name, bases) = match rest.split_once(':') {
+            Some((name, bases)) => (name.trim(), split_generic_args(bases)),
+            None => (rest.trim(), Vec::new()),
+        };
+        // generic definitions can't be serialized as they're written
+        if name.contains('<') {
+            attributes.clear();
+            continue;
+        }
+
+        current = Some(Decl {
+            namespace: namespace.clone(),
+            name: name.to_string(),
+            kind,
+            bases: bases.into_iter().map(str::to_string).collect(),
+            attributes: std::mem::take(&mut attributes),
+            fields: Vec::new(),
+            members: Vec::new(),
+            backing: String::from("int"),
+        });
+    }
+
+    decls
+}
+
+fn parse_field(decl: &mut Decl, line: &str, attributes: &[String]) {
+    let line = line.trim().trim_end_matches(';');
+    if has_attribute(attributes, "NonSerialized") {
+        return;
+    }
+
+    let (line, is_static) = strip_modifiers(line);
+    let (line, value) = match line.split_once(" = ") {
+        Some((line, value)) => (line.trim(), Some(value.trim())),
+        None => (line, None),
+    };
+    let Some((type_name, name)) = line.rsplit_once(' ') else {
+        return;
+    };
+
+    if decl.kind == DeclKind::Enum {
+        match (name, value) {
+            ("value__", _) => decl.backing = type_name.to_string(),
+            (name, Some(value)) if is_static => {
+                decl.members.push((name.to_string(), value.to_string()))
+            }
+            _ => {}
+        }
+    } else if !is_static && !name.contains('<') {
+        decl.fields
+            .push((type_name.trim().to_string(), name.to_string()));
+    }
+}
+
+/// Splits leading `[Attribute]` groups off a line.
+fn split_attributes(mut line: &str) -> (Vec<String>, &str) {
+    let mut attributes = Vec::new();
+    while let Some(rest) = line.strip_prefix('[') {
+        let Some(end) = rest.find(']') else {
+            break;
+        };
+        attributes.extend(
+            split_generic_args(&rest[..end])
+                .into_iter()
+                .map(normalize_attribute),
+        );
+        line = rest[end + 1..].trim_start();
+    }
+    (attributes, line)
+}
+
+/// `[System.FlagsAttribute]` and `[Flags]` are the same attribute, keep the
+/// short name so they compare equal. Arguments are kept as they are.
+fn normalize_attribute(attr: &str) -> String {
+    let (name, args) = match attr.split_once('(') {
+        Some((name, args)) => (name, Some(args)),
+        None => (attr, None),
+    };
+    let name = name.trim();
+    let name = name.rsplit('.').next().unwrap_or(name);
+    let name = name.strip_suffix("Attribute").unwrap_or(name);
+    match args {
+        Some(args) => format!("{name}({args}"),
+        None => name.to_string(),
+    }
+}
+
+#[inline]
+fn has_attribute(attributes: &[String], attribute: &str) -> bool {
+    attributes
+        .iter()
+        .any(|attr| attr.split_once('(').map_or(attr.as_str(), |(name, _)| name) == attribute)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataDefine, ValueKind, dump_cs};
+
+    const DUMP: &str = r#"
+// Namespace: RPG.GameCore
+public enum AvatarBaseType // TypeDefIndex: 1
+{
+	// Fields
+	public byte value__; // 0x0
+	public const AvatarBaseType Unknown = 0;
+	public const AvatarBaseType Warrior = 1;
+}
+
+// Namespace: RPG.GameCore
+[FlagsAttribute]
+public enum TargetFlags // TypeDefIndex: 5
+{
+	// Fields
+	public int value__; // 0x0
+	public const TargetFlags Self = 1;
+	public const TargetFlags Ally = 2;
+}
+
+// Namespace: RPG.GameCore
+public abstract class TaskConfig // TypeDefIndex: 2
+{
+	// Fields
+	public string Name; // 0x10
+}
+
+// Namespace: RPG.GameCore
+[RPG.GameCore.TypeIndexAttribute(4)]
+public class WaitTask : TaskConfig // TypeDefIndex: 3
+{
+	// Fields
+	public float Time; // 0x18
+	[NonSerialized]
+	public int Cache; // 0x1C
+	[System.NonSerialized]
+	public int Cache2; // 0x20
+	private static int s_Count; // 0x0
+
+	// Methods
+
+	// RVA: 0x1 Offset: 0x1 VA: 0x1
+	public void .ctor() { }
+}
+
+// Namespace: RPG.GameCore
+public class AvatarExcelRow : IExcelRow // TypeDefIndex: 4
+{
+	// Fields
+	public uint AvatarID; // 0x10
+	public AvatarBaseType BaseType; // 0x14
+	public List<TaskConfig> Tasks; // 0x18
+	public Dictionary<int, string[]> Names; // 0x20
+}
+"#;
+
+    #[test]
+    fn test_generate_schema() {
+        let schema = dump_cs::generate_schema(DUMP, &Default::default());
+        assert!(schema.unresolved.is_empty(), "{:?}", schema.unresolved);
+
+        let Some(DataDefine::Enum(backing, members)) =
+            schema.types.get("RPG.GameCore.AvatarBaseType")
+        else {
+            panic!("enum not generated");
+        };
+        assert_eq!(backing, "byte");
+        assert_eq!(members["1"], "Warrior");
+        assert!(matches!(
+            schema.types.get("RPG.GameCore.TargetFlags"),
+            Some(DataDefine::FlagsEnum(..))
+        ));
+
+        let Some(DataDefine::Class { fields, .. }) = schema.types.get("RPG.GameCore.WaitTask")
+        else {
+            panic!("class not generated");
+        };
+        let names = fields
+            .iter()
+            .map(|f| f.field_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Name", "Time"]);
+
+        let Some(DataDefine::Typeindex { descendants, .. }) =
+            schema.types.get("RPG.GameCore.TaskConfig")
+        else {
+            panic!("typeindex not generated");
+        };
+        assert_eq!(
+            descendants[&4],
+            ValueKind::Class(String::from("RPG.GameCore.WaitTask"))
+        );
+
+        let Some(DataDefine::Class { fields, .. }) =
+            schema.types.get("RPG.GameCore.AvatarExcelRow")
+        else {
+            panic!("row not generated");
+        };
+        assert_eq!(
+            fields[2].data_type,
+            ValueKind::Array(Box::new(ValueKind::Class(String::from(
+                "RPG.GameCore.TaskConfig"
+            ))))
+        );
+        assert_eq!(
+            schema.excel_paths["RPG.GameCore.AvatarExcelRow"],
+            [
+                "BakedConfig/ExcelOutput/AvatarExcel.bytes",
+                "BakedConfig/ExcelOutput/Avatar.bytes"
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+use varint_rs::VarintReader;
+
+use crate::DynamicParser;
+
+impl<'a> DynamicParser<'a> {
+    pub(crate) fn parse_enum(
+        &mut self,
+        enum_name: &str,
+        enum_type: &str,
+        enums: &BTreeMap<String, String>,
+        flags: bool,
+    ) -> anyhow::Result<Value> {
+        let discriminant = self.read_integral(enum_type)?;
+
+        let name = if flags {
+            self.decode_flags(enum_name, enum_type, enums, discriminant)
+        } else if let Some(name) = enums.get(&discriminant.to_string()) {
+            self.report.enum_hits += 1;
+            Value::String(name.clone())
+        } else {
+            self.report.enum_misses += 1;
+            self.report
+                .unknown_enums
+                .entry(enum_name.to_string())
+                .or_default()
+                .insert(discriminant);
+            Value::String(discriminant.to_string())
+        };
+
+        Ok(if self.options.enum_with_value {
+            json!({
+                "Name": name,
+                "Value": number(discriminant),
+            })
+        } else {
+            name
+        })
+    }
+
+    /// Reads a varint of any C# integral type.
+    pub(crate) fn read_integral(&mut self, type_name: &str) -> anyhow::Result<i128> {
+        Ok(match type_name {
+            "byte" => self.cursor.read_u8_varint()? as i128,
+            "sbyte" => self.cursor.read_i8_varint()? as i128,
+            "short" => self.cursor.read_i16_varint()? as i128,
+            "ushort" => self.cursor.read_u16_varint()? as i128,
+            "int" => self.cursor.read_i32_varint()? as i128,
+            "uint" => self.cursor.read_u32_varint()? as i128,
+            "long" => self.cursor.read_i64_varint()? as i128,
+            "ulong" => self.cursor.read_u64_varint()? as i128,
+            _ => {
+                return Err(anyhow::format_err!(
+                    "unsupported integral type: {}",
+                    type_name
+                ));
+            }
+        })
+    }
+
+    /// Splits a `[Flags]` value into member names, highest member first.
+    /// Bits no member covers are appended as a number.
+    fn decode_flags(
+        &mut self,
+        enum_name: &str,
+        enum_type: &str,
+        enums: &BTreeMap<String, String>,
+        discriminant: i128,
+    ) -> Value {
+        let bits = backing_bits(enum_type);
+        let mask = |v: i128| (v as u128) & (u128::MAX >> (128 - bits));
+
+        let mut members = enums
+            .iter()
+            .filter_map(|(value, name)| Some((mask(value.parse::<i128>().ok()?), name)))
+            .collect::<Vec<_>>();
+        members.sort_by_key(|(value, _)| std::cmp::Reverse(*value));
+
+        let value = mask(discriminant);
+        if value == 0 {
+            // no flags set is a valid value with or without a member for it
+            self.report.enum_hits += 1;
+            return match members.iter().find(|(v, _)| *v == 0) {
+                Some((_, name)) => json!([name]),
+                None => json!([]),
+            };
+        }
+
+        let mut names = Vec::new();
+        let mut leftover = value;
+        for (member, name) in members {
+            if member != 0 && value & member == member && leftover & member != 0 {
+                names.push(Value::String(name.clone()));
+                leftover &= !member;
+            }
+        }
+
+        if leftover == 0 {
+            self.report.enum_hits += 1;
+        } else {
+            self.report.enum_misses += 1;
+            self.report
+                .unknown_enums
+                .entry(enum_name.to_string())
+                .or_default()
+                .insert(leftover as i128);
+            names.push(number(leftover as i128));
+        }
+
+        Value::Array(names)
+    }
+}
+
+#[inline]
+fn backing_bits(enum_type: &str) -> u32 {
+    match enum_type {
+        "byte" | "sbyte" => 8,
+        "short" | "ushort" => 16,
+        "int" | "uint" => 32,
+        _ => 64,
+    }
+}
+
+#[inline]
+pub(crate) fn number(value: i128) -> Value {
+    if let Ok(value) = i64::try_from(value) {
+        Value::Number(value.into())
+    } else if let Ok(value) = u64::try_from(value) {
+        Value::Number(value.into())
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataDefine, DynamicParser, ParseOptions, ValueKind};
+    use serde_json::json;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_flags_enum() {
+        let members = BTreeMap::from([
+            (String::from("0"), String::from("None")),
+            (String::from("1"), String::from("A")),
+            (String::from("2"), String::from("B")),
+            (String::from("3"), String::from("AB")),
+        ]);
+        let types = HashMap::from([(
+            String::from("Flags"),
+            DataDefine::FlagsEnum(String::from("byte"), members),
+        )]);
+        let kind = ValueKind::Class(String::from("Flags"));
+
+        let bytes = vec![7];
+        let mut parser = DynamicParser::new(&types, &bytes);
+        assert_eq!(parser.parse(&kind, false).unwrap(), json!(["AB", 4]));
+        assert!(parser.report.unknown_enums["Flags"].contains(&4));
+
+        let options = ParseOptions {
+            enum_with_value: true,
+            ..Default::default()
+        };
+        let bytes = vec![0];
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        assert_eq!(
+            parser.parse(&kind, false).unwrap(),
+            json!({ "Name": ["None"], "Value": 0 })
+        );
+        assert_eq!(parser.report.enum_hits, 1);
+
+        // zero without a member for it
+        let types = HashMap::from([(
+            String::from("Flags"),
+            DataDefine::FlagsEnum(
+                String::from("byte"),
+                BTreeMap::from([(String::from("1"), String::from("A"))]),
+            ),
+        )]);
+        let mut parser = DynamicParser::new(&types, &bytes);
+        assert_eq!(parser.parse(&kind, false).unwrap(), json!([]));
+        assert_eq!((parser.report.enum_hits, parser.report.enum_misses), (1, 0));
+    }
+}
@@ -0,0 +1,160 @@
+use std::{collections::HashMap, fmt::Write as _};
+
+use crate::{DataDefine, ParseOptions};
+
+use super::{Cond, Count, Field, FieldType, Schema, TypeDef, build};
+
+const PRELUDE: &str = r#"import std.mem;
+import std.string;
+
+struct VarInt {
+    u8 bytes[while(std::mem::read_unsigned($, 1) & 0x80)];
+    u8 last;
+};
+
+fn varint(ref VarInt v) {
+    u64 result = 0;
+    u64 shift = 0;
+    for (u64 i = 0, i < sizeof(v.bytes), i += 1) {
+        result |= u64(v.bytes[i] & 0x7F) << shift;
+        shift += 7;
+    }
+    return result | (u64(v.last) << shift);
+};
+
+fn zigzag(u64 value) {
+    return s64(value >> 1) ^ -s64(value & 1);
+};
+
+fn format_uint(ref VarInt v) {
+    return varint(v);
+};
+
+fn format_sint(ref VarInt v) {
+    return zigzag(varint(v));
+};
+"#;
+
+/// ImHex pattern of `root`, placed at the start of the file.
+pub fn to_hexpat(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    root: &str,
+) -> anyhow::Result<String> {
+    let schema = build(types, options, root)?;
+    Ok(write(&schema)?)
+}
+
+fn write(schema: &Schema) -> Result<String, std::fmt::Error> {
+    let mut out = String::from(PRELUDE);
+
+    writeln!(out)?;
+    for (name, _) in &schema.types {
+        writeln!(out, "using {name};")?;
+    }
+
+    for (name, def) in &schema.types {
+        writeln!(out)?;
+        match def {
+            TypeDef::Seq(fields) => {
+                writeln!(out, "struct {name} {{")?;
+                for field in fields {
+                    writeln!(out, "    {}", field_decl(field))?;
+                }
+                writeln!(out, "}};")?;
+            }
+            TypeDef::Switch(cases) => {
+                writeln!(out, "struct {name} {{")?;
+                writeln!(out, "    VarInt typeindex;")?;
+                for (i, (typeindex, case)) in cases.iter().enumerate() {
+                    let keyword = if i == 0 { "if" } else { "else if" };
+                    writeln!(
+                        out,
+                        "    {keyword} (varint(typeindex) == {typeindex}) {case} body;"
+                    )?;
+                }
+                writeln!(out, "}};")?;
+            }
+            TypeDef::Enum {
+                signed,
+                flags,
+                members,
+            } => {
+                writeln!(out, "fn format_{name}(ref auto v) {{")?;
+                if *signed {
+                    writeln!(out, "    s64 value = zigzag(varint(v.raw));")?;
+                } else {
+                    writeln!(out, "    u64 value = varint(v.raw);")?;
+                }
+                if *flags {
+                    writeln!(out, "    str out = std::format(\"{{}}\", value);")?;
+                    for (value, member) in members.iter().filter(|(value, _)| *value != 0) {
+                        writeln!(
+                            out,
+                            "    if ((value & {value}) == {value}) out = out + \" | {member}\";"
+                        )?;
+                    }
+                    writeln!(out, "    return out;")?;
+                } else {
+                    for (value, member) in members {
+                        writeln!(out, "    if (value == {value}) return \"{member}\";")?;
+                    }
+                    writeln!(out, "    return std::format(\"{{}}\", value);")?;
+                }
+                writeln!(out, "}};")?;
+                writeln!(out)?;
+                writeln!(out, "struct {name} {{")?;
+                writeln!(out, "    VarInt raw;")?;
+                writeln!(out, "}} [[format(\"format_{name}\")]];")?;
+            }
+            TypeDef::Opaque => {
+                writeln!(out, "// Read by a custom parser, not exported.")?;
+                writeln!(out, "struct {name} {{}};")?;
+            }
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(out, "{} root @ 0x00;", schema.root)?;
+
+    Ok(out)
+}
+
+fn field_decl(field: &Field) -> String {
+    let id = &field.id;
+    let mut decl = match &field.ty {
+        FieldType::Varint { .. } => format!("VarInt {id}"),
+        FieldType::U1 => format!("u8 {id}"),
+        FieldType::F4 => format!("float {id}"),
+        FieldType::F8 => format!("double {id}"),
+        FieldType::Str(count) => format!("char {id}[{}]", count_expr(count)),
+        FieldType::Raw(count) => format!("u8 {id}[{}]", count_expr(count)),
+        FieldType::Named(name) => format!("{name} {id}"),
+    };
+    if let Some(count) = &field.repeat {
+        write!(decl, "[{}]", count_expr(count)).unwrap();
+    }
+    if let (FieldType::Varint { signed }, None) = (&field.ty, &field.repeat) {
+        let format = if *signed {
+            "format_sint"
+        } else {
+            "format_uint"
+        };
+        write!(decl, " [[format(\"{format}\")]]").unwrap();
+    }
+    decl.push(';');
+
+    match &field.cond {
+        Some(Cond::Bit(flag, bit)) => format!("if ((varint({flag}) >> {bit}) & 1) {decl}"),
+        Some(Cond::Flag(flag)) => format!("if ({flag} != 0) {decl}"),
+        None => decl,
+    }
+}
+
+fn count_expr(count: &Count) -> String {
+    match count {
+        Count::Field { id, signed: false } => format!("varint({id})"),
+        Count::Field { id, signed: true } => format!("zigzag(varint({id}))"),
+        Count::Fixed(count) => count.to_string(),
+    }
+}
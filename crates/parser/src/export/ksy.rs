@@ -0,0 +1,146 @@
+use std::{collections::HashMap, fmt::Write as _};
+
+use crate::{DataDefine, ParseOptions};
+
+use super::{Cond, Count, FieldType, Schema, TypeDef, build, snake_case};
+
+/// Kaitai Struct definition of `root`, using the common `vlq_base128_le`
+/// import for varints.
+pub fn to_ksy(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    root: &str,
+) -> anyhow::Result<String> {
+    let schema = build(types, options, root)?;
+    Ok(write(&schema)?)
+}
+
+fn write(schema: &Schema) -> Result<String, std::fmt::Error> {
+    let mut out = String::new();
+    writeln!(out, "meta:")?;
+    writeln!(out, "  id: {}_file", snake_case(&schema.root))?;
+    writeln!(out, "  endian: le")?;
+    writeln!(out, "  imports:")?;
+    writeln!(out, "    - /common/vlq_base128_le")?;
+    writeln!(out, "seq:")?;
+    writeln!(out, "  - id: root")?;
+    writeln!(out, "    type: {}", snake_case(&schema.root))?;
+
+    writeln!(out, "types:")?;
+    for (name, def) in &schema.types {
+        writeln!(out, "  {}:", snake_case(name))?;
+        match def {
+            TypeDef::Seq(fields) if fields.is_empty() => writeln!(out, "    seq: []")?,
+            TypeDef::Seq(fields) => {
+                writeln!(out, "    seq:")?;
+                for field in fields {
+                    writeln!(out, "      - id: {}", snake_case(&field.id))?;
+                    match &field.ty {
+                        FieldType::Varint { .. } => writeln!(out, "        type: vlq_base128_le")?,
+                        FieldType::U1 => writeln!(out, "        type: u1")?,
+                        FieldType::F4 => writeln!(out, "        type: f4")?,
+                        FieldType::F8 => writeln!(out, "        type: f8")?,
+                        FieldType::Str(count) => {
+                            writeln!(out, "        type: str")?;
+                            writeln!(out, "        size: {}", count_expr(count))?;
+                            writeln!(out, "        encoding: UTF-8")?;
+                        }
+                        FieldType::Raw(count) => {
+                            writeln!(out, "        size: {}", count_expr(count))?
+                        }
+                        FieldType::Named(name) => {
+                            writeln!(out, "        type: {}", snake_case(name))?
+                        }
+                    }
+                    match &field.cond {
+                        Some(Cond::Bit(flag, bit)) => writeln!(
+                            out,
+                            "        if: ({}.value >> {bit}) & 1 != 0",
+                            snake_case(flag)
+                        )?,
+                        Some(Cond::Flag(flag)) => {
+                            writeln!(out, "        if: {} != 0", snake_case(flag))?
+                        }
+                        None => {}
+                    }
+                    if let Some(count) = &field.repeat {
+                        writeln!(out, "        repeat: expr")?;
+                        writeln!(out, "        repeat-expr: {}", count_expr(count))?;
+                    }
+                }
+            }
+            TypeDef::Switch(cases) => {
+                writeln!(out, "    seq:")?;
+                writeln!(out, "      - id: typeindex")?;
+                writeln!(out, "        type: vlq_base128_le")?;
+                writeln!(out, "      - id: body")?;
+                writeln!(out, "        type:")?;
+                writeln!(out, "          switch-on: typeindex.value")?;
+                writeln!(out, "          cases:")?;
+                for (typeindex, name) in cases {
+                    writeln!(out, "            {typeindex}: {}", snake_case(name))?;
+                }
+            }
+            TypeDef::Enum { signed, flags, .. } => {
+                writeln!(out, "    seq:")?;
+                writeln!(out, "      - id: raw")?;
+                writeln!(out, "        type: vlq_base128_le")?;
+                writeln!(out, "    instances:")?;
+                writeln!(out, "      value:")?;
+                let value = Count::Field {
+                    id: String::from("raw"),
+                    signed: *signed,
+                };
+                writeln!(out, "        value: {}", count_expr(&value))?;
+                if !flags {
+                    writeln!(out, "        enum: {}", snake_case(name))?;
+                }
+            }
+            TypeDef::Opaque => {
+                writeln!(out, "    doc: Read by a custom parser, not exported.")?;
+                writeln!(out, "    seq: []")?;
+            }
+        }
+    }
+
+    let enums = schema
+        .types
+        .iter()
+        .filter_map(|(name, def)| match def {
+            TypeDef::Enum {
+                flags: false,
+                members,
+                ..
+            } => Some((name, members)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !enums.is_empty() {
+        writeln!(out, "enums:")?;
+        for (name, members) in enums {
+            writeln!(out, "  {}:", snake_case(name))?;
+            let mut seen = Vec::with_capacity(members.len());
+            for (value, member) in members {
+                let mut id = snake_case(member);
+                if seen.contains(&id) {
+                    id = format!("{id}_{}", value.unsigned_abs());
+                }
+                writeln!(out, "    {value}: {id}")?;
+                seen.push(id);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn count_expr(count: &Count) -> String {
+    match count {
+        Count::Field { id, signed: false } => format!("{}.value", snake_case(id)),
+        Count::Field { id, signed: true } => {
+            let id = snake_case(id);
+            format!("({id}.value >> 1) ^ -({id}.value & 1)")
+        }
+        Count::Fixed(count) => count.to_string(),
+    }
+}
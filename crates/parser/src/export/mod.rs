@@ -0,0 +1,441 @@
+//! Turns the schema reachable from a root type into hex editor / parser
+//! generator definitions, so a problem file can be stepped through with the
+//! current schema applied.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    DataDefine, ParseOptions, ValueKind, custom_parser::CUSTOM_PARSER,
+    typeindex::resolve_descendant,
+};
+
+mod hexpat;
+mod ksy;
+
+pub use hexpat::to_hexpat;
+pub use ksy::to_ksy;
+
+/// Flattened form of the schema that both exporters are written against.
+/// Every non-trivial `ValueKind` becomes a named type, so each field is a
+/// single type reference with an optional condition and repeat.
+struct Schema {
+    root: String,
+    /// In dependency order, each type after everything it refers to.
+    types: Vec<(String, TypeDef)>,
+}
+
+enum TypeDef {
+    Seq(Vec<Field>),
+    /// Typeindex: a varint discriminant then the matching body.
+    Switch(Vec<(u64, String)>),
+    Enum {
+        signed: bool,
+        flags: bool,
+        members: Vec<(i128, String)>,
+    },
+    /// Parsed by a hand-written or declarative custom parser.
+    Opaque,
+}
+
+struct Field {
+    id: String,
+    ty: FieldType,
+    cond: Option<Cond>,
+    repeat: Option<Count>,
+}
+
+enum FieldType {
+    Varint { signed: bool },
+    U1,
+    F4,
+    F8,
+    Str(Count),
+    Raw(Count),
+    Named(String),
+}
+
+enum Cond {
+    /// Bit of an `ExistFlag` varint field.
+    Bit(String, usize),
+    /// A `U1` field that is non-zero.
+    Flag(String),
+}
+
+enum Count {
+    /// Read from an earlier varint field, zigzag decoded when `signed`.
+    Field {
+        id: String,
+        signed: bool,
+    },
+    Fixed(usize),
+}
+
+impl Field {
+    fn new(id: &str, ty: FieldType) -> Self {
+        Self {
+            id: id.to_string(),
+            ty,
+            cond: None,
+            repeat: None,
+        }
+    }
+}
+
+struct Builder<'a> {
+    types: &'a HashMap<String, DataDefine>,
+    custom: &'a HashSet<String>,
+    done: HashSet<String>,
+    out: Vec<(String, TypeDef)>,
+}
+
+fn build(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    root: &str,
+) -> anyhow::Result<Schema> {
+    let custom = CUSTOM_PARSER
+        .keys()
+        .map(|name| name.to_string())
+        .chain(options.layouts.keys().cloned())
+        .collect();
+    let mut builder = Builder {
+        types,
+        custom: &custom,
+        done: HashSet::new(),
+        out: Vec::new(),
+    };
+    let root = match builder.field_type(&ValueKind::Class(root.to_string()))? {
+        FieldType::Named(name) => name,
+        _ => unreachable!("classes are always named"),
+    };
+
+    Ok(Schema {
+        root,
+        types: builder.out,
+    })
+}
+
+impl Builder<'_> {
+    /// Marks `name` as in progress, returning false when it's already known.
+    /// Types are pushed once built, so self references only need the name.
+    fn start(&mut self, name: &str) -> bool {
+        self.done.insert(name.to_string())
+    }
+
+    fn field_type(&mut self, kind: &ValueKind) -> anyhow::Result<FieldType> {
+        Ok(match kind {
+            ValueKind::Primitive(name) => match name.as_str() {
+                "byte" | "ushort" | "uint" | "ulong" | "char" => {
+                    FieldType::Varint { signed: false }
+                }
+                "sbyte" | "short" | "int" | "long" => FieldType::Varint { signed: true },
+                "bool" => FieldType::U1,
+                "float" => FieldType::F4,
+                "double" => FieldType::F8,
+                "decimal" => FieldType::Raw(Count::Fixed(16)),
+                "string" => {
+                    if self.start("string") {
+                        let def = TypeDef::Seq(vec![
+                            Field::new("length", FieldType::Varint { signed: false }),
+                            Field::new(
+                                "value",
+                                FieldType::Str(Count::Field {
+                                    id: String::from("length"),
+                                    signed: false,
+                                }),
+                            ),
+                        ]);
+                        self.out.push((String::from("string"), def));
+                    }
+                    FieldType::Named(String::from("string"))
+                }
+                other => return Err(anyhow::format_err!("unhandled primitive: {other}")),
+            },
+            ValueKind::Class(class_name) => {
+                let name = type_name(kind);
+                if self.start(&name) {
+                    let def = self.class_def(class_name)?;
+                    self.out.push((name.clone(), def));
+                }
+                FieldType::Named(name)
+            }
+            _ => {
+                let name = type_name(kind);
+                if self.start(&name) {
+                    let def = self.container_def(kind)?;
+                    self.out.push((name.clone(), def));
+                }
+                FieldType::Named(name)
+            }
+        })
+    }
+
+    fn container_def(&mut self, kind: &ValueKind) -> anyhow::Result<TypeDef> {
+        let length = || Field::new("length", FieldType::Varint { signed: true });
+        let by_length = || Count::Field {
+            id: String::from("length"),
+            signed: true,
+        };
+        let floats = |names: &[&str]| {
+            TypeDef::Seq(names.iter().map(|n| Field::new(n, FieldType::F4)).collect())
+        };
+
+        Ok(match kind {
+            ValueKind::Array(item) | ValueKind::Set(item) => {
+                let mut items = Field::new("items", self.field_type(item)?);
+                items.repeat = Some(by_length());
+                TypeDef::Seq(vec![length(), items])
+            }
+            ValueKind::Dictionary(key, value) => {
+                let entry = format!("{}_entry", type_name(kind));
+                if self.start(&entry) {
+                    let def = TypeDef::Seq(vec![
+                        Field::new("key", self.field_type(key)?),
+                        Field::new("value", self.field_type(value)?),
+                    ]);
+                    self.out.push((entry.clone(), def));
+                }
+                let mut entries = Field::new("entries", FieldType::Named(entry));
+                entries.repeat = Some(by_length());
+                TypeDef::Seq(vec![length(), entries])
+            }
+            ValueKind::Nullable(value) => {
+                let mut field = Field::new("value", self.field_type(value)?);
+                field.cond = Some(Cond::Flag(String::from("has_value")));
+                TypeDef::Seq(vec![Field::new("has_value", FieldType::U1), field])
+            }
+            ValueKind::FixedArray(item, count) => {
+                let mut items = Field::new("items", self.field_type(item)?);
+                items.repeat = Some(Count::Fixed(*count));
+                TypeDef::Seq(vec![items])
+            }
+            ValueKind::Bytes => TypeDef::Seq(vec![
+                length(),
+                Field::new("data", FieldType::Raw(by_length())),
+            ]),
+            ValueKind::Vector2 => floats(&["x", "y"]),
+            ValueKind::Vector3 => floats(&["x", "y", "z"]),
+            ValueKind::Vector4 | ValueKind::Quaternion => floats(&["x", "y", "z", "w"]),
+            ValueKind::Color => floats(&["r", "g", "b", "a"]),
+            ValueKind::Primitive(_) | ValueKind::Class(_) | ValueKind::Other() => {
+                return Err(anyhow::format_err!("unknown data kind! {kind:?}"));
+            }
+        })
+    }
+
+    fn class_def(&mut self, class_name: &str) -> anyhow::Result<TypeDef> {
+        if self.custom.contains(class_name) {
+            return Ok(TypeDef::Opaque);
+        }
+
+        let Some(define) = self.types.get(class_name) else {
+            return Err(anyhow::format_err!("unhandled type: {class_name}"));
+        };
+
+        Ok(match define {
+            DataDefine::Class {
+                skip_existflag_check: Some(_),
+                ..
+            } => TypeDef::Seq(Vec::new()),
+            DataDefine::Class { fields, .. } => {
+                let flag_count = fields.len().max(1).div_ceil(64);
+                let mut out = (0..flag_count)
+                    .map(|i| {
+                        Field::new(
+                            &format!("exist_flag_{i}"),
+                            FieldType::Varint { signed: false },
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                for (i, field) in fields.iter().enumerate() {
+                    let mut out_field =
+                        Field::new(&field.field_name, self.field_type(&field.data_type)?);
+                    out_field.cond = Some(Cond::Bit(format!("exist_flag_{}", i / 64), i % 64));
+                    out.push(out_field);
+                }
+                TypeDef::Seq(out)
+            }
+            DataDefine::Struct { fields, .. } => TypeDef::Seq(
+                fields
+                    .iter()
+                    .map(|field| {
+                        Ok(Field::new(
+                            &field.field_name,
+                            self.field_type(&field.data_type)?,
+                        ))
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            DataDefine::Typeindex { descendants, .. } => {
+                let types = self.types;
+                let mut cases = Vec::with_capacity(descendants.len());
+                for (typeindex, kind) in descendants {
+                    // a nested Typeindex is read as its default descendant,
+                    // without a discriminant of its own
+                    let kind = resolve_descendant(types, kind);
+                    if let FieldType::Named(name) = self.field_type(kind)? {
+                        cases.push((*typeindex, name));
+                    }
+                }
+                TypeDef::Switch(cases)
+            }
+            DataDefine::Enum(backing, members) | DataDefine::FlagsEnum(backing, members) => {
+                TypeDef::Enum {
+                    signed: matches!(backing.as_str(), "sbyte" | "short" | "int" | "long"),
+                    flags: matches!(define, DataDefine::FlagsEnum(..)),
+                    members: members
+                        .iter()
+                        .filter_map(|(value, name)| Some((value.parse().ok()?, name.clone())))
+                        .collect(),
+                }
+            }
+        })
+    }
+}
+
+/// Stable name for the type generated for `kind`.
+fn type_name(kind: &ValueKind) -> String {
+    match kind {
+        ValueKind::Primitive(name) => name.clone(),
+        ValueKind::Class(name) => name.replace(['.', '`', '<', '>', ',', ' ', '+'], "_"),
+        ValueKind::Array(item) => format!("array_{}", type_name(item)),
+        ValueKind::Set(item) => format!("set_{}", type_name(item)),
+        ValueKind::Dictionary(key, value) => {
+            format!("dict_{}_{}", type_name(key), type_name(value))
+        }
+        ValueKind::Nullable(value) => format!("nullable_{}", type_name(value)),
+        ValueKind::FixedArray(item, count) => format!("fixed{count}_{}", type_name(item)),
+        ValueKind::Bytes => String::from("bytes"),
+        ValueKind::Vector2 => String::from("vector2"),
+        ValueKind::Vector3 => String::from("vector3"),
+        ValueKind::Vector4 => String::from("vector4"),
+        ValueKind::Quaternion => String::from("quaternion"),
+        ValueKind::Color => String::from("color"),
+        ValueKind::Other() => String::from("other"),
+    }
+}
+
+/// Lowercase snake case identifier, as Kaitai requires and ImHex tolerates.
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && prev_lower {
+                out.push('_');
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            out.push(c.to_ascii_lowercase());
+        } else {
+            if !out.ends_with('_') {
+                out.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+
+    let out = out.trim_matches('_').to_string();
+    match out.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => out,
+        _ => format!("t_{out}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataDefine, DataField, DynamicParser, ParseOptions, ValueKind, export};
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_export_schema() {
+        let types = HashMap::from([
+            (
+                String::from("RPG.Row"),
+                DataDefine::Class {
+                    skip_existflag_check: None,
+                    fields: vec![
+                        DataField {
+                            field_name: String::from("ID"),
+                            data_type: ValueKind::Primitive(String::from("uint")),
+                        },
+                        DataField {
+                            field_name: String::from("Names"),
+                            data_type: ValueKind::Array(Box::new(ValueKind::Primitive(
+                                String::from("string"),
+                            ))),
+                        },
+                        DataField {
+                            field_name: String::from("Kind"),
+                            data_type: ValueKind::Class(String::from("RPG.Kind")),
+                        },
+                    ],
+                    interfaces: Vec::new(),
+                },
+            ),
+            (
+                String::from("RPG.Kind"),
+                DataDefine::Enum(
+                    String::from("int"),
+                    BTreeMap::from([(String::from("1"), String::from("Big"))]),
+                ),
+            ),
+        ]);
+
+        let ksy = export::to_ksy(&types, &ParseOptions::default(), "RPG.Row").unwrap();
+        assert!(ksy.contains("  id: rpg_row_file\n"));
+        assert!(ksy.contains("if: (exist_flag_0.value >> 1) & 1 != 0"));
+        assert!(ksy.contains("repeat-expr: (length.value >> 1) ^ -(length.value & 1)"));
+        assert!(ksy.contains("    1: big"));
+
+        let hexpat = export::to_hexpat(&types, &ParseOptions::default(), "RPG.Row").unwrap();
+        assert!(hexpat.contains("struct RPG_Row {"));
+        assert!(hexpat.contains("if ((varint(exist_flag_0) >> 1) & 1) array_string Names;"));
+        assert!(hexpat.contains("RPG_Row root @ 0x00;"));
+    }
+
+    #[test]
+    fn test_export_nested_typeindex() {
+        let class = |fields: &[&str]| DataDefine::Struct {
+            fields: fields
+                .iter()
+                .map(|name| DataField {
+                    field_name: name.to_string(),
+                    data_type: ValueKind::Primitive(String::from("int")),
+                })
+                .collect(),
+            interfaces: Vec::new(),
+        };
+        let typeindex = |descendants: &[(u64, &str)]| DataDefine::Typeindex {
+            base: String::from("RPG.Task"),
+            descendants: descendants
+                .iter()
+                .map(|(i, name)| (*i, ValueKind::Class(name.to_string())))
+                .collect(),
+        };
+        // RPG.Mid is abstract, the parser reads it as its descendant 0
+        let types = HashMap::from([
+            (
+                String::from("RPG.Task"),
+                typeindex(&[(1, "RPG.Mid"), (2, "RPG.TaskB")]),
+            ),
+            (String::from("RPG.Mid"), typeindex(&[(0, "RPG.TaskA")])),
+            (String::from("RPG.TaskA"), class(&["A"])),
+            (String::from("RPG.TaskB"), class(&["B"])),
+        ]);
+
+        let ksy = export::to_ksy(&types, &ParseOptions::default(), "RPG.Task").unwrap();
+        assert!(ksy.contains("            1: rpg_task_a\n"));
+        assert!(!ksy.contains("rpg_mid"));
+
+        let hexpat = export::to_hexpat(&types, &ParseOptions::default(), "RPG.Task").unwrap();
+        assert!(hexpat.contains("if (varint(typeindex) == 1) RPG_TaskA body;"));
+        assert!(!hexpat.contains("RPG_Mid"));
+
+        // the parser reads the same bytes the same way
+        let bytes = vec![1, 6];
+        let parsed = DynamicParser::new(&types, &bytes)
+            .parse(&ValueKind::Class(String::from("RPG.Task")), false)
+            .unwrap();
+        assert_eq!(parsed["A"], 3);
+    }
+}
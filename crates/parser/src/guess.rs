@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+};
+
+use serde::Serialize;
+use tg_asset_meta::baked_header::{ConfigHeader, ExcelHeader};
+
+use crate::{DataDefine, DynamicParser, ParseOptions, ValueKind};
+
+/// Header handling tried for every candidate type, matching what the CLI
+/// strips from Excel and Config blobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GuessVariant {
+    Raw,
+    /// After an [`ExcelHeader`], tried only when it reads as one.
+    SkipZeroByte,
+    /// After a [`ConfigHeader`], tried only when the blob has one.
+    Skip12Bytes,
+}
+
+impl GuessVariant {
+    pub const ALL: [GuessVariant; 3] = [Self::Raw, Self::SkipZeroByte, Self::Skip12Bytes];
+
+    fn apply(self, bytes: &[u8]) -> Option<&[u8]> {
+        match self {
+            Self::Raw => Some(bytes),
+            Self::SkipZeroByte => ExcelHeader::split(bytes).ok().map(|(_, rows)| rows),
+            Self::Skip12Bytes => match ConfigHeader::split(bytes) {
+                Ok((Some(_), payload)) => Some(payload),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TypeGuess {
+    pub kind: ValueKind,
+    pub variant: GuessVariant,
+    pub consumed: usize,
+    pub total: usize,
+    pub enum_hits: usize,
+    pub enum_misses: usize,
+    pub implausible_lengths: usize,
+    pub empty_reads: usize,
+    pub error: Option<String>,
+    pub score: f64,
+}
+
+impl TypeGuess {
+    #[inline]
+    pub fn fully_consumed(&self) -> bool {
+        self.error.is_none() && self.consumed == self.total
+    }
+}
+
+/// Ranks every class and struct in `types`, and every class in
+/// `options.layouts`, by how well it parses `bytes`, both as a single value and
+/// as an Excel-style array, best guess first.
+///
+/// Guesses that error out are kept at the bottom, as how far they got is still
+/// a hint when nothing fits.
+pub fn guess_type(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    bytes: &[u8],
+) -> Vec<TypeGuess> {
+    let candidates = types
+        .iter()
+        .filter(|(_, define)| !matches!(define, DataDefine::Enum(..) | DataDefine::FlagsEnum(..)))
+        .map(|(name, _)| name)
+        .chain(
+            options
+                .layouts
+                .keys()
+                .filter(|name| !types.contains_key(*name)),
+        )
+        .collect::<Vec<_>>();
+    let mut guesses = Vec::new();
+
+    for variant in GuessVariant::ALL {
+        let Some(data) = variant.apply(bytes) else {
+            continue;
+        };
+        if variant != GuessVariant::Raw && data.is_empty() {
+            continue;
+        }
+        let data = data.to_vec();
+
+        for name in &candidates {
+            let class = ValueKind::Class((*name).clone());
+            for kind in [class.clone(), ValueKind::Array(Box::new(class))] {
+                guesses.push(try_kind(types, options, &data, kind, variant));
+            }
+        }
+    }
+
+    guesses.sort_by(|a, b| b.score.total_cmp(&a.score));
+    guesses
+}
+
+fn try_kind(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    data: &Vec<u8>,
+    kind: ValueKind,
+    variant: GuessVariant,
+) -> TypeGuess {
+    let mut parser = DynamicParser::with_options(types, data, options);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| parser.parse(&kind, false)));
+    let error = match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(_) => Some(String::from("parser panicked")),
+    };
+
+    let report = &parser.report;
+    let mut guess = TypeGuess {
+        kind,
+        variant,
+        consumed: parser.cursor.position() as usize,
+        total: data.len(),
+        enum_hits: report.enum_hits,
+        enum_misses: report.enum_misses,
+        implausible_lengths: report.implausible_lengths,
+        empty_reads: report.empty_reads,
+        error,
+        score: 0.0,
+    };
+    guess.score = score(&guess);
+    guess
+}
+
+fn score(guess: &TypeGuess) -> f64 {
+    let ratio = if guess.total == 0 {
+        1.0
+    } else {
+        guess.consumed as f64 / guess.total as f64
+    };
+
+    let mut score = ratio * 50.0;
+    if guess.error.is_none() {
+        score += 100.0;
+    }
+    if guess.fully_consumed() {
+        score += 50.0;
+    }
+
+    let enum_total = guess.enum_hits + guess.enum_misses;
+    if enum_total > 0 {
+        score += 10.0 * guess.enum_hits as f64 / enum_total as f64;
+    }
+
+    score - 10.0 * (guess.implausible_lengths + guess.empty_reads) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GuessVariant, ParseOptions, ValueKind, guess_type};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_variants_split_headers() {
+        let excel = [0, 4, 1, 1];
+        assert_eq!(Some(&excel[1..]), GuessVariant::SkipZeroByte.apply(&excel));
+        // an unknown Excel version isn't stripped
+        assert_eq!(None, GuessVariant::SkipZeroByte.apply(&[2, 4, 1, 1]));
+
+        let config = [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 7, 8];
+        assert_eq!(Some(&[7, 8][..]), GuessVariant::Skip12Bytes.apply(&config));
+        // twelve bytes or more, but no payload size to match
+        assert_eq!(None, GuessVariant::Skip12Bytes.apply(&config[..13]));
+    }
+
+    #[test]
+    fn test_guess_layout_class() {
+        let options = ParseOptions {
+            layouts: serde_json::from_value(json!({
+                "TextID": [{ "Field": ["Hash", { "Varint": "int" }] }]
+            }))
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let guesses = guess_type(&HashMap::new(), &options, &[3]);
+        let best = &guesses[0];
+        assert_eq!(best.kind, ValueKind::Class(String::from("TextID")));
+        assert!(best.error.is_none());
+        assert_eq!(best.consumed, best.total);
+    }
+}
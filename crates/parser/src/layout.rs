@@ -0,0 +1,263 @@
+//! Declarative layouts for hand-serialized types, so a new one can be added
+//! from a JSON file next to `data.json` instead of in `custom_parser.rs`.
+//!
+//! ```json
+//! {
+//!     "RPG.GameCore.ReadInfo": [
+//!         { "If": { "then": [
+//!             { "Field": ["AKFKONMJCEC", "String"] },
+//!             { "Field": ["EGMAFIOOKJJ", { "Varint": "long" }] }
+//!         ] } }
+//!     ]
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read as _,
+};
+
+use base64::Engine as _;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tg_bytes_util::FromBytes;
+
+use crate::{DynamicParser, ValueKind, enums::number};
+
+/// Layouts by the class name they parse, as loaded from `custom_parsers.json`.
+pub type Layouts = HashMap<String, Vec<LayoutOp>>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum LayoutOp {
+    /// Reads a value into the named field.
+    Field(String, LayoutValue),
+    /// Reads a value and drops it.
+    Skip(LayoutValue),
+    /// Reads a bool, then runs `then` or `else` depending on it. The flag
+    /// itself is kept when `name` is set.
+    If {
+        name: Option<String>,
+        then: Vec<LayoutOp>,
+        #[serde(default, rename = "else")]
+        otherwise: Vec<LayoutOp>,
+    },
+    /// Branches on a field read earlier in the same layout.
+    Match {
+        field: String,
+        cases: BTreeMap<String, Vec<LayoutOp>>,
+        #[serde(default)]
+        default: Vec<LayoutOp>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum LayoutValue {
+    /// A raw byte.
+    Byte,
+    Bool,
+    /// Little-endian f32.
+    Float,
+    /// Little-endian f64.
+    Double,
+    String,
+    /// Varint of a C# integral type, e.g. `int` or `ulong`.
+    Varint(String),
+    /// `count` raw bytes, emitted as base64.
+    Bytes(LayoutCount),
+    Array(LayoutCount, Box<LayoutValue>),
+    /// A nested layout, emitted as an object.
+    Struct(Vec<LayoutOp>),
+    /// Any schema kind, including classes from `data.json` and other layouts.
+    Schema(ValueKind),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum LayoutCount {
+    Byte,
+    Varint(String),
+    Fixed(usize),
+}
+
+impl<'a> DynamicParser<'a> {
+    pub(crate) fn parse_layout(&mut self, ops: &[LayoutOp]) -> anyhow::Result<Value> {
+        let mut output = Map::new();
+        self.run_layout_ops(ops, &mut output)?;
+        Ok(Value::Object(output))
+    }
+
+    fn run_layout_ops(
+        &mut self,
+        ops: &[LayoutOp],
+        output: &mut Map<String, Value>,
+    ) -> anyhow::Result<()> {
+        for op in ops {
+            match op {
+                LayoutOp::Field(name, value) => {
+                    let value = self.read_layout_value(value)?;
+                    output.insert(name.clone(), value);
+                }
+                LayoutOp::Skip(value) => {
+                    self.read_layout_value(value)?;
+                }
+                LayoutOp::If {
+                    name,
+                    then,
+                    otherwise,
+                } => {
+                    let flag = bool::from_bytes(&mut self.cursor)?;
+                    if let Some(name) = name {
+                        output.insert(name.clone(), Value::Bool(flag));
+                    }
+                    self.run_layout_ops(if flag { then } else { otherwise }, output)?;
+                }
+                LayoutOp::Match {
+                    field,
+                    cases,
+                    default,
+                } => {
+                    let key = match output.get(field) {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(value) => value.to_string(),
+                        None => {
+                            return Err(anyhow::format_err!(
+                                "layout matches on unread field {field}"
+                            ));
+                        }
+                    };
+                    self.run_layout_ops(cases.get(&key).unwrap_or(default), output)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_layout_value(&mut self, value: &LayoutValue) -> anyhow::Result<Value> {
+        Ok(match value {
+            LayoutValue::Byte => {
+                let mut buf = [0u8; 1];
+                self.cursor.read_exact(&mut buf)?;
+                Value::Number(buf[0].into())
+            }
+            LayoutValue::Bool => Value::Bool(bool::from_bytes(&mut self.cursor)?),
+            LayoutValue::Float => self.read_float()?,
+            LayoutValue::Double => {
+                self.parse(&ValueKind::Primitive(String::from("double")), false)?
+            }
+            LayoutValue::String => Value::String(String::from_bytes(&mut self.cursor)?),
+            LayoutValue::Varint(type_name) => number(self.read_integral(type_name)?),
+            LayoutValue::Bytes(count) => {
+                let length = self.read_layout_count(count)?;
+                if length > self.remaining() {
+                    return Err(anyhow::format_err!(
+                        "layout bytes length {length} exceeds remaining buffer"
+                    ));
+                }
+                let mut buf = vec![0u8; length];
+                self.cursor.read_exact(&mut buf)?;
+                Value::String(base64::engine::general_purpose::STANDARD.encode(buf))
+            }
+            LayoutValue::Array(count, item) => {
+                let length = self.read_layout_count(count)?;
+                let mut output = Vec::with_capacity(length);
+                for _ in 0..length {
+                    output.push(self.read_layout_value(item)?);
+                }
+                Value::Array(output)
+            }
+            LayoutValue::Struct(ops) => self.parse_layout(ops)?,
+            LayoutValue::Schema(kind) => self.parse(kind, false)?,
+        })
+    }
+
+    fn read_layout_count(&mut self, count: &LayoutCount) -> anyhow::Result<usize> {
+        let length = match count {
+            LayoutCount::Byte => {
+                let mut buf = [0u8; 1];
+                self.cursor.read_exact(&mut buf)?;
+                buf[0] as usize
+            }
+            LayoutCount::Varint(type_name) => self.read_integral(type_name)?.max(0) as usize,
+            LayoutCount::Fixed(length) => *length,
+        };
+
+        if length > 1_000_000 {
+            return Err(anyhow::format_err!("attempting to allocate large memory!"));
+        }
+
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        DynamicParser, ParseOptions, TypeEmit, TypeNamePolicy, ValueKind, layout::Layouts,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_layout_parser() {
+        let layouts: Layouts = serde_json::from_value(json!({
+            "TextID": [
+                { "Field": ["Hash", { "Varint": "int" }] },
+                { "Field": ["Kind", "Byte"] },
+                { "Match": { "field": "Kind", "cases": {
+                    "1": [{ "Field": ["Values", { "Array": ["Byte", "Float"] }] }]
+                } } },
+                { "If": { "name": "HasName", "then": [{ "Field": ["Name", "String"] }] } }
+            ]
+        }))
+        .unwrap();
+        let options = ParseOptions {
+            layouts,
+            ..Default::default()
+        };
+
+        let mut bytes = vec![3, 1, 1];
+        bytes.extend(0.5f32.to_le_bytes());
+        bytes.extend([2, 2, b'h', b'i']);
+
+        let types = HashMap::new();
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        let parsed = parser
+            .parse(&ValueKind::Class(String::from("TextID")), false)
+            .unwrap();
+
+        assert_eq!(
+            parsed,
+            json!({ "Hash": -2, "Kind": 1, "Values": [0.5], "HasName": true, "Name": "hi" })
+        );
+        assert_eq!(0, parser.remaining());
+    }
+
+    #[test]
+    fn test_layout_type_name() {
+        let layouts: Layouts = serde_json::from_value(json!({
+            "RPG.GameCore.TextIDInner": [{ "Field": ["Hash", { "Varint": "int" }] }]
+        }))
+        .unwrap();
+        let options = ParseOptions {
+            layouts,
+            type_names: TypeNamePolicy {
+                emit: TypeEmit::Always,
+                qualified: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let bytes = vec![3];
+        let types = HashMap::new();
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        let parsed = parser
+            .parse(
+                &ValueKind::Class(String::from("RPG.GameCore.TextIDInner")),
+                false,
+            )
+            .unwrap();
+
+        assert_eq!(parsed, json!({ "$type": "TextID", "Hash": -2 }));
+    }
+}
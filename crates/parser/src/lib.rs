@@ -1,16 +1,33 @@
-use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::io::Cursor;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Cursor, Read as _};
+use std::sync::LazyLock;
+
+use base64::Engine as _;
 
 use anyhow::Context;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_json::{Map, Value};
 use tg_bytes_util::{ExistFlag, FromBytes};
 use varint_rs::VarintReader;
 mod custom_parser;
-
-#[derive(Debug, Deserialize)]
+pub mod dump_cs;
+mod enums;
+pub mod export;
+mod guess;
+mod layout;
+mod options;
+mod trace;
+mod typeindex;
+
+pub use guess::{GuessVariant, TypeGuess, guess_type};
+pub use layout::{LayoutCount, LayoutOp, LayoutValue, Layouts};
+pub use options::{DictionaryMode, ParseOptions, TypeEmit, TypeNamePolicy};
+pub use trace::{Trace, TraceFailure, TraceSpan};
+pub use typeindex::{ProbeCandidate, TypeindexProbe};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum DataDefine {
     Class {
         skip_existflag_check: Option<bool>,
@@ -26,47 +43,126 @@ pub enum DataDefine {
         descendants: BTreeMap<u64, ValueKind>,
     },
     Enum(String, BTreeMap<String, String>),
+    /// `[Flags]` enum, decoded into the names of every member set.
+    FlagsEnum(String, BTreeMap<String, String>),
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DataField {
     pub field_name: String,
     pub data_type: ValueKind,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub enum ValueKind {
     Primitive(String),
     Array(Box<ValueKind>),
     Dictionary(Box<ValueKind>, Box<ValueKind>),
     Class(String),
+    /// `T?`, a bool presence flag followed by the value.
+    Nullable(Box<ValueKind>),
+    /// `fixed T[N]` and friends, `N` values without a length prefix.
+    FixedArray(Box<ValueKind>, usize),
+    /// `HashSet<T>`, serialized like an array.
+    Set(Box<ValueKind>),
+    /// `byte[]`, emitted as a base64 string.
+    Bytes,
+    Vector2,
+    Vector3,
+    Vector4,
+    Quaternion,
+    Color,
     Other(),
 }
 
+static DEFAULT_OPTIONS: LazyLock<ParseOptions> = LazyLock::new(ParseOptions::default);
+
+/// Things noticed during a parse that don't stop it, but point at schema drift.
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    pub typeindex_probes: Vec<TypeindexProbe>,
+    /// Enum discriminants found in the schema.
+    pub enum_hits: usize,
+    /// Enum discriminants missing from the schema.
+    pub enum_misses: usize,
+    /// Every discriminant (or leftover flag bits) missing from the schema, by enum type.
+    pub unknown_enums: BTreeMap<String, BTreeSet<i128>>,
+    /// Array or dictionary lengths larger than the bytes left to read them from.
+    pub implausible_lengths: usize,
+    /// Values defaulted because the buffer ran out before they were read.
+    pub empty_reads: usize,
+}
+
+impl ParseReport {
+    /// Adds what another part of the same parse noticed.
+    pub(crate) fn merge(&mut self, other: ParseReport) {
+        self.typeindex_probes.extend(other.typeindex_probes);
+        self.enum_hits += other.enum_hits;
+        self.enum_misses += other.enum_misses;
+        for (enum_type, values) in other.unknown_enums {
+            self.unknown_enums
+                .entry(enum_type)
+                .or_default()
+                .extend(values);
+        }
+        self.implausible_lengths += other.implausible_lengths;
+        self.empty_reads += other.empty_reads;
+    }
+}
+
 pub struct DynamicParser<'a> {
     pub types: &'a HashMap<String, DataDefine>,
     pub cursor: Cursor<&'a Vec<u8>>,
+    pub options: &'a ParseOptions,
+    pub report: ParseReport,
+    probing: bool,
+    class_stack: Vec<&'a str>,
+    trace: Option<Trace>,
 }
 
 impl<'a> DynamicParser<'a> {
     pub fn new(types: &'a HashMap<String, DataDefine>, data: &'a Vec<u8>) -> Self {
+        Self::with_options(types, data, &DEFAULT_OPTIONS)
+    }
+
+    pub fn with_options(
+        types: &'a HashMap<String, DataDefine>,
+        data: &'a Vec<u8>,
+        options: &'a ParseOptions,
+    ) -> Self {
         Self {
             types,
             cursor: Cursor::new(data),
+            options,
+            report: ParseReport::default(),
+            probing: false,
+            class_stack: Vec::new(),
+            trace: None,
         }
     }
 
     pub fn parse(&mut self, kind: &ValueKind, include_type: bool) -> anyhow::Result<Value> {
+        if self.trace.is_some() && !self.probing {
+            return self.parse_traced(kind, include_type);
+        }
+
+        self.parse_value(kind, include_type)
+    }
+
+    fn parse_value(&mut self, kind: &ValueKind, include_type: bool) -> anyhow::Result<Value> {
         if self.remaining() < 1 {
             tracing::debug!("{:?} buffer is empty", kind);
+            self.report.empty_reads += 1;
 
             return Ok(match kind {
                 ValueKind::Primitive(_) => Value::Number(0.into()),
-                ValueKind::Array(_) => Value::Array(Vec::with_capacity(0)),
-                ValueKind::Dictionary(_, _) | ValueKind::Class(_) => {
-                    Value::Object(Map::with_capacity(0))
+                ValueKind::Array(_) | ValueKind::FixedArray(_, _) | ValueKind::Set(_) => {
+                    Value::Array(Vec::with_capacity(0))
                 }
-                ValueKind::Other() => Value::Null,
+                ValueKind::Bytes => Value::String(String::with_capacity(0)),
+                ValueKind::Nullable(_) | ValueKind::Other() => Value::Null,
+                ValueKind::Dictionary(_, _) => self.collect_dictionary(Vec::with_capacity(0)),
+                _ => Value::Object(Map::with_capacity(0)),
             });
         }
 
@@ -80,21 +176,57 @@ impl<'a> DynamicParser<'a> {
                 "uint" => Value::Number(self.cursor.read_u32_varint()?.into()),
                 "long" => Value::Number(self.cursor.read_i64_varint()?.into()),
                 "ulong" => Value::Number(serde_json::Number::from(self.cursor.read_u64_varint()?)),
-                "float" => {
-                    let raw = f32::from_bytes(&mut self.cursor)? as f64;
-                    let sanitized = if raw.is_finite() { raw } else { 0.0 };
-                    let number = serde_json::Number::from_f64(sanitized)
-                        .ok_or_else(|| anyhow::anyhow!("float should always be finite"))?;
-                    Value::Number(number)
-                }
+                "float" => self.read_float()?,
                 "double" => Value::Number(
                     serde_json::Number::from_f64(f64::from_bytes(&mut self.cursor)?)
                         .ok_or_else(|| anyhow::anyhow!("invalid double"))?,
                 ),
                 "bool" => Value::Bool(bool::from_bytes(&mut self.cursor)?),
                 "string" => Value::String(String::from_bytes(&mut self.cursor)?),
+                "char" => Value::String(
+                    char::from_u32(self.cursor.read_u16_varint()? as u32)
+                        .unwrap_or(char::REPLACEMENT_CHARACTER)
+                        .to_string(),
+                ),
+                "decimal" => self.read_decimal()?,
                 other => return Err(anyhow::format_err!("unhandled primitive: {other}")),
             },
+            ValueKind::Nullable(value) => {
+                let start = self.cursor.position();
+                let has_value = bool::from_bytes(&mut self.cursor)?;
+                self.trace_mark(start, "HasValue", || has_value.to_string());
+                if has_value {
+                    self.parse(value, false)?
+                } else {
+                    Value::Null
+                }
+            }
+            ValueKind::FixedArray(value, length) => {
+                let mut output = Vec::with_capacity(*length);
+                for i in 0..*length {
+                    self.trace_enter(|| format!("[{i}]"));
+                    output.push(self.parse(value, false)?);
+                    self.trace_leave();
+                }
+                Value::Array(output)
+            }
+            ValueKind::Bytes => {
+                let length = self.cursor.read_i64_varint()? as usize;
+                if length > self.remaining() {
+                    return Err(anyhow::format_err!(
+                        "byte[] length {length} exceeds remaining buffer"
+                    ));
+                }
+                let mut buf = vec![0u8; length];
+                self.cursor.read_exact(&mut buf)?;
+                Value::String(base64::engine::general_purpose::STANDARD.encode(buf))
+            }
+            ValueKind::Vector2 => self.read_floats(&["X", "Y"])?,
+            ValueKind::Vector3 => self.read_floats(&["X", "Y", "Z"])?,
+            ValueKind::Vector4 | ValueKind::Quaternion => {
+                self.read_floats(&["X", "Y", "Z", "W"])?
+            }
+            ValueKind::Color => self.read_floats(&["R", "G", "B", "A"])?,
             ValueKind::Dictionary(key, value) => {
                 tracing::debug!(
                     "ValueKind::Dictionary(cursor_pos: {}) -> Dictionary<{:?}, {:?}>",
@@ -103,7 +235,9 @@ impl<'a> DynamicParser<'a> {
                     value
                 );
 
+                let start = self.cursor.position();
                 let length = self.cursor.read_i64_varint()? as usize;
+                self.trace_mark(start, "Length", || length.to_string());
 
                 tracing::debug!(
                     "ValueKind::Dictionary(cursor_pos: {}) -> Dictionary length: {}",
@@ -111,34 +245,31 @@ impl<'a> DynamicParser<'a> {
                     length
                 );
 
+                if length > self.remaining() {
+                    self.report.implausible_lengths += 1;
+                }
+
                 if length > 1_000_000 {
                     return Err(anyhow::format_err!("attempting to allocate large memory!"));
                 }
 
-                let mut output = Map::with_capacity(length);
-
-                for _ in 0..length {
-                    let key = self.parse(key, false)?;
-                    output.insert(
-                        if let Value::String(s) = key {
-                            s
-                        } else {
-                            key.to_string()
-                        },
-                        self.parse(value, false)?,
-                    );
+                let mut entries = Vec::with_capacity(length);
+                for i in 0..length {
+                    entries.push(self.parse_entry(i, key, value)?);
                 }
 
-                Value::Object(output)
+                self.collect_dictionary(entries)
             }
-            ValueKind::Array(value) => {
+            ValueKind::Array(value) | ValueKind::Set(value) => {
                 tracing::debug!(
                     "ValueKind::Array(cursor_pos: {}) -> {:?}[]",
                     self.cursor.position(),
                     value
                 );
 
+                let start = self.cursor.position();
                 let length = self.cursor.read_i64_varint()? as usize;
+                self.trace_mark(start, "Length", || length.to_string());
 
                 tracing::debug!(
                     "ValueKind::Array(cursor_pos: {}) -> Array length: {}",
@@ -146,14 +277,20 @@ impl<'a> DynamicParser<'a> {
                     length
                 );
 
+                if length > self.remaining() {
+                    self.report.implausible_lengths += 1;
+                }
+
                 if length > 1_000_000 {
                     return Err(anyhow::format_err!("attempting to allocate large memory!"));
                 }
 
                 let mut output = Vec::with_capacity(length);
 
-                for _ in 0..length {
+                for i in 0..length {
+                    self.trace_enter(|| format!("[{i}]"));
                     output.push(self.parse(value, false)?);
+                    self.trace_leave();
                 }
 
                 Value::Array(output)
@@ -165,27 +302,37 @@ impl<'a> DynamicParser<'a> {
                     class_name
                 );
 
-                if let Some(custom) = custom_parser::CUSTOM_PARSER.get(class_name.as_str()) {
-                    return custom(self);
-                }
-
-                let Some(define) = self.types.get(class_name) else {
-                    return Err(anyhow::format_err!("unhandled type: {}", &class_name));
+                let options = self.options;
+                let result = if let Some(layout) = options.layouts.get(class_name) {
+                    self.parse_layout(layout)
+                } else if let Some((class_name, custom)) =
+                    custom_parser::CUSTOM_PARSER.get_key_value(class_name.as_str())
+                {
+                    // so per-class dictionary modes apply to custom classes too
+                    self.class_stack.push(class_name);
+                    let result = custom(self);
+                    self.class_stack.pop();
+                    result
+                } else {
+                    let types = self.types;
+                    let Some((class_name, define)) = types.get_key_value(class_name) else {
+                        return Err(anyhow::format_err!("unhandled type: {}", &class_name));
+                    };
+
+                    self.class_stack.push(class_name);
+                    let result = self.parse_class_kind(class_name, define);
+                    self.class_stack.pop();
+                    result
                 };
+                let mut result = result?;
 
-                let mut result = self.parse_class_kind(define)?;
-
-                if include_type {
+                let type_names = &self.options.type_names;
+                if type_names.should_emit(include_type) {
                     result.as_object_mut().and_then(|f| {
                         f.shift_insert(
                             0,
-                            "$type".into(),
-                            Value::String(
-                                class_name
-                                    .strip_suffix("Inner")
-                                    .unwrap_or(class_name)
-                                    .to_string(),
-                            ),
+                            type_names.key.clone(),
+                            Value::String(type_names.display_name(class_name)),
                         )
                     });
                 }
@@ -196,7 +343,11 @@ impl<'a> DynamicParser<'a> {
         })
     }
 
-    fn parse_class_kind(&mut self, data_type: &DataDefine) -> anyhow::Result<Value> {
+    fn parse_class_kind(
+        &mut self,
+        class_name: &str,
+        data_type: &'a DataDefine,
+    ) -> anyhow::Result<Value> {
         Ok(match data_type {
             DataDefine::Class {
                 skip_existflag_check,
@@ -207,7 +358,26 @@ impl<'a> DynamicParser<'a> {
                     return Ok(json!({}));
                 }
 
+                let start = self.cursor.position();
                 let exist_flag = ExistFlag::new(&mut self.cursor, fields.len())?;
+                self.trace_mark(start, "ExistFlag", || {
+                    let (present, absent): (Vec<_>, Vec<_>) = fields
+                        .iter()
+                        .enumerate()
+                        .partition(|(i, _)| exist_flag.exists(*i));
+                    let names = |fields: Vec<(usize, &DataField)>| {
+                        fields
+                            .iter()
+                            .map(|(_, field)| field.field_name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    match (present.is_empty(), absent.is_empty()) {
+                        (_, true) => format!("present: {}", names(present)),
+                        (true, _) => format!("absent: {}", names(absent)),
+                        _ => format!("present: {}; absent: {}", names(present), names(absent)),
+                    }
+                });
                 let mut output = Map::with_capacity(fields.len());
                 for (i, field) in fields.iter().enumerate() {
                     if exist_flag.exists(i) {
@@ -217,7 +387,9 @@ impl<'a> DynamicParser<'a> {
                             field.field_name
                         );
 
+                        self.trace_enter(|| field.field_name.clone());
                         let value = self.parse(&field.data_type, false)?;
+                        self.trace_leave();
 
                         tracing::debug!(
                             "DataDefine::Class(cursor_pos: {}) -> Value: {:?}",
@@ -248,7 +420,9 @@ impl<'a> DynamicParser<'a> {
                         field.field_name
                     );
 
+                    self.trace_enter(|| field.field_name.clone());
                     let value = self.parse(&field.data_type, false)?;
+                    self.trace_leave();
 
                     tracing::debug!(
                         "DataDefine::Struct(cursor_pos: {}) -> Value: {:?}",
@@ -266,12 +440,21 @@ impl<'a> DynamicParser<'a> {
                     self.cursor.position()
                 );
 
+                let start = self.cursor.position();
                 let typeindex = self
                     .cursor
                     .read_u64_varint()
                     .context("typeindex reading failed")?;
+                self.trace_mark(start, "Typeindex", || match descendants.get(&typeindex) {
+                    Some(descendant) => format!("{typeindex} ({descendant})"),
+                    None => format!("{typeindex} (unknown)"),
+                });
 
                 let Some(descendant) = descendants.get(&typeindex) else {
+                    if self.options.probe_typeindex && !self.probing {
+                        return self.probe_typeindex(base, typeindex);
+                    }
+
                     return Err(anyhow::format_err!(
                         "typeindex not exist! dict: {:?} type index: {}",
                         descendants,
@@ -279,87 +462,269 @@ impl<'a> DynamicParser<'a> {
                     ));
                 };
 
-                if let ValueKind::Class(descendant) = descendant
-                    && let Some(DataDefine::Typeindex {
-                        base: _,
-                        descendants,
-                    }) = self.types.get(descendant)
-                    && let Some(descendant) = descendants.get(&0)
-                {
-                    return self.parse(descendant, true);
-                }
-
                 tracing::debug!(
                     "DataDefine::Typeindex(cursor_pos: {}) -> {} typeindex: {typeindex}",
                     self.cursor.position(),
                     base
                 );
 
-                return self.parse(descendant, true);
+                return self.parse(typeindex::resolve_descendant(self.types, descendant), true);
             }
             DataDefine::Enum(enum_type, enums) => {
-                let enum_value = match enum_type.as_str() {
-                    "ulong" => {
-                        let discriminant = self.cursor.read_u64_varint()?;
-                        if let Some(enum_value) = enums.get(&discriminant.to_string()) {
-                            enum_value
-                        } else {
-                            tracing::debug!(
-                                "enum_value not exist! enums: {:?} discriminant: {}",
-                                enums,
-                                discriminant
-                            );
-                            &discriminant.to_string()
-                        }
-                    }
-                    "int" => {
-                        let discriminant = self.cursor.read_i32_varint()?;
-                        if let Some(discriminant) = enums.get(&discriminant.to_string()) {
-                            discriminant
-                        } else {
-                            tracing::warn!(
-                                "enum_value not exist! enums: {:?} discriminant: {}",
-                                enums,
-                                discriminant
-                            );
-                            &format!("{discriminant}")
-                        }
-                    }
-                    "uint" => {
-                        let discriminant = self.cursor.read_u32_varint()?;
-                        if let Some(discriminant) = enums.get(&discriminant.to_string()) {
-                            discriminant
-                        } else {
-                            tracing::warn!(
-                                "enum_value not exist! enums: {:?} discriminant: {}",
-                                enums,
-                                discriminant
-                            );
-                            &format!("{discriminant}")
-                        }
-                    }
-                    "ushort" => {
-                        let discriminant = self.cursor.read_u16_varint()?;
-                        if let Some(discriminant) = enums.get(&discriminant.to_string()) {
-                            discriminant
-                        } else {
-                            tracing::debug!(
-                                "enum_value not exist! enums: {:?} discriminant: {}",
-                                enums,
-                                discriminant
-                            );
-                            &format!("{discriminant}")
-                        }
-                    }
-                    _ => return Err(anyhow::format_err!("unsupported enum type: {}", enum_type)),
-                };
-                Value::String(enum_value.into())
+                self.parse_enum(class_name, enum_type, enums, false)?
+            }
+            DataDefine::FlagsEnum(enum_type, enums) => {
+                self.parse_enum(class_name, enum_type, enums, true)?
             }
         })
     }
 
+    fn parse_entry(
+        &mut self,
+        index: usize,
+        key: &ValueKind,
+        value: &ValueKind,
+    ) -> anyhow::Result<(Value, Value)> {
+        self.trace_enter(|| format!("[{index}]"));
+        self.trace_enter(|| String::from("Key"));
+        let key = self.parse(key, false)?;
+        self.trace_leave();
+        self.trace_enter(|| String::from("Value"));
+        let value = self.parse(value, false)?;
+        self.trace_leave();
+        self.trace_leave();
+        Ok((key, value))
+    }
+
+    /// Builds a dictionary the way `dictionary_mode` asks for: an object
+    /// keyed by each key's string form, or an array of `{"Key", "Value"}`
+    /// keeping keys typed. Custom parsers reading dictionaries go through
+    /// here too, so every writer agrees.
+    pub(crate) fn collect_dictionary(&self, entries: Vec<(Value, Value)>) -> Value {
+        match self.dictionary_mode() {
+            DictionaryMode::Object => Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let key = match key {
+                            Value::String(s) => s,
+                            key => key.to_string(),
+                        };
+                        (key, value)
+                    })
+                    .collect(),
+            ),
+            DictionaryMode::KeyValueArray => Value::Array(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        json!({
+                            "Key": key,
+                            "Value": value,
+                        })
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[inline]
+    fn dictionary_mode(&self) -> DictionaryMode {
+        self.class_stack
+            .last()
+            .and_then(|class_name| self.options.dictionary_modes.get(*class_name))
+            .copied()
+            .unwrap_or(self.options.dictionary_mode)
+    }
+
+    fn read_float(&mut self) -> anyhow::Result<Value> {
+        let raw = f32::from_bytes(&mut self.cursor)? as f64;
+        let sanitized = if raw.is_finite() { raw } else { 0.0 };
+        let number = serde_json::Number::from_f64(sanitized)
+            .ok_or_else(|| anyhow::anyhow!("float should always be finite"))?;
+        Ok(Value::Number(number))
+    }
+
+    fn read_floats(&mut self, names: &[&str]) -> anyhow::Result<Value> {
+        let mut output = Map::with_capacity(names.len());
+        for name in names {
+            output.insert(name.to_string(), self.read_float()?);
+        }
+        Ok(Value::Object(output))
+    }
+
+    /// System.Decimal in its in-memory layout: 96-bit mantissa (lo, mid, hi)
+    /// then flags holding the scale and sign. Emitted as a string to keep
+    /// every digit.
+    fn read_decimal(&mut self) -> anyhow::Result<Value> {
+        let mut parts = [0u32; 4];
+        for part in &mut parts {
+            let mut buf = [0u8; 4];
+            self.cursor.read_exact(&mut buf)?;
+            *part = u32::from_le_bytes(buf);
+        }
+        let [lo, mid, hi, flags] = parts;
+
+        let mantissa = ((hi as u128) << 64) | ((mid as u128) << 32) | lo as u128;
+        let scale = ((flags >> 16) & 0xff) as usize;
+        if scale > 28 {
+            return Err(anyhow::format_err!("invalid decimal scale: {scale}"));
+        }
+
+        let digits = format!("{mantissa:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        let sign = if flags & 0x8000_0000 != 0 { "-" } else { "" };
+
+        Ok(Value::String(if frac.is_empty() {
+            format!("{sign}{int}")
+        } else {
+            format!("{sign}{int}.{frac}")
+        }))
+    }
+
     #[inline]
-    fn remaining(&self) -> usize {
+    pub fn remaining(&self) -> usize {
         self.cursor.get_ref().len() - self.cursor.position() as usize
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        DataDefine, DataField, DictionaryMode, DynamicParser, ParseOptions, TypeEmit,
+        TypeNamePolicy, ValueKind,
+    };
+    use std::collections::HashMap;
+
+    fn parse(kind: ValueKind, bytes: Vec<u8>) -> serde_json::Value {
+        let types = HashMap::new();
+        let mut parser = DynamicParser::new(&types, &bytes);
+        let value = parser.parse(&kind, false).unwrap();
+        assert_eq!(0, parser.remaining());
+        value
+    }
+
+    #[test]
+    fn test_extended_kinds() {
+        let int = || Box::new(ValueKind::Primitive(String::from("int")));
+
+        assert_eq!(
+            parse(ValueKind::Nullable(int()), vec![0]),
+            serde_json::Value::Null
+        );
+        assert_eq!(parse(ValueKind::Nullable(int()), vec![1, 4]), 2);
+        assert_eq!(
+            parse(ValueKind::FixedArray(int(), 2), vec![2, 3]),
+            serde_json::json!([1, -2])
+        );
+        assert_eq!(
+            parse(ValueKind::Set(int()), vec![2, 6]),
+            serde_json::json!([3])
+        );
+        assert_eq!(parse(ValueKind::Bytes, vec![4, 1, 2]), "AQI=");
+        assert_eq!(
+            parse(ValueKind::Primitive(String::from("char")), vec![0x41]),
+            "A"
+        );
+
+        // 12345 with a scale of 2, negative
+        let mut decimal = 12345u32.to_le_bytes().to_vec();
+        decimal.extend([0; 8]);
+        decimal.extend(0x8002_0000u32.to_le_bytes());
+        assert_eq!(
+            parse(ValueKind::Primitive(String::from("decimal")), decimal),
+            "-123.45"
+        );
+
+        let mut vector = 1.5f32.to_le_bytes().to_vec();
+        vector.extend(2f32.to_le_bytes());
+        assert_eq!(
+            parse(ValueKind::Vector2, vector),
+            serde_json::json!({ "X": 1.5, "Y": 2.0 })
+        );
+    }
+
+    #[test]
+    fn test_dictionary_modes() {
+        let kind = ValueKind::Dictionary(
+            Box::new(ValueKind::Primitive(String::from("int"))),
+            Box::new(ValueKind::Primitive(String::from("bool"))),
+        );
+        let bytes = vec![2, 6, 2];
+        let types = HashMap::new();
+
+        assert_eq!(
+            parse(kind.clone(), bytes.clone()),
+            serde_json::json!({ "3": true })
+        );
+
+        let options = ParseOptions {
+            dictionary_mode: DictionaryMode::KeyValueArray,
+            ..Default::default()
+        };
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        assert_eq!(
+            parser.parse(&kind, false).unwrap(),
+            serde_json::json!([{ "Key": 3, "Value": true }])
+        );
+
+        // past the end of the buffer, in the mode that applies
+        let empty = Vec::new();
+        let mut parser = DynamicParser::with_options(&types, &empty, &options);
+        assert_eq!(parser.parse(&kind, false).unwrap(), serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_custom_parser_dictionary_mode() {
+        let types = HashMap::from([(
+            String::from("RPG.GameCore.StringHash"),
+            DataDefine::Struct {
+                fields: vec![DataField {
+                    field_name: String::from("Hash"),
+                    data_type: ValueKind::Primitive(String::from("int")),
+                }],
+                interfaces: Vec::new(),
+            },
+        )]);
+        // one entry: StringHash { 3 }, then a fixed float of 0 without read info
+        let bytes = vec![1, 6, 0, 0, 0, 0];
+        let kind = ValueKind::Class(String::from("LAHCFFKCOBC"));
+
+        let options = ParseOptions {
+            dictionary_modes: HashMap::from([(
+                String::from("LAHCFFKCOBC"),
+                DictionaryMode::KeyValueArray,
+            )]),
+            ..Default::default()
+        };
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        let parsed = parser.parse(&kind, false).unwrap();
+        assert_eq!(parsed["Floats"][0]["Key"]["Hash"], 3);
+        assert_eq!(parsed["Floats"][0]["Value"]["v24"]["Value"], 0.0);
+        assert_eq!(0, parser.remaining());
+    }
+
+    #[test]
+    fn test_custom_parser_type_name() {
+        let options = ParseOptions {
+            type_names: TypeNamePolicy {
+                emit: TypeEmit::Always,
+                qualified: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // an Int32 of 0
+        let bytes = vec![0, 0];
+        let types = HashMap::new();
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        let parsed = parser
+            .parse(
+                &ValueKind::Class(String::from("RPG.GameCore.DynamicValue")),
+                false,
+            )
+            .unwrap();
+        assert_eq!(parsed["$type"], "DynamicValue");
+        assert_eq!(parsed["Type"], "Int32");
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Layouts;
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Try every class derived from the same base when a Typeindex discriminant
+    /// is missing from the schema, instead of failing the whole parse.
+    pub probe_typeindex: bool,
+    /// Emit enums as `{Name, Value}` rather than just the member name.
+    pub enum_with_value: bool,
+    pub dictionary_mode: DictionaryMode,
+    /// Per-class override of `dictionary_mode`, applied to the dictionaries
+    /// that are direct fields of that class.
+    pub dictionary_modes: HashMap<String, DictionaryMode>,
+    pub type_names: TypeNamePolicy,
+    /// Declarative parsers by class name, tried before the built-in custom
+    /// parsers and the schema.
+    pub layouts: Layouts,
+}
+
+/// How `Dictionary` values are written out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DictionaryMode {
+    /// A JSON object, with non-string keys stringified.
+    #[default]
+    Object,
+    /// `[{"Key": ..., "Value": ...}]`, keeping keys typed.
+    KeyValueArray,
+}
+
+/// Which classes get a type discriminator field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TypeEmit {
+    /// Only classes reached through a Typeindex.
+    #[default]
+    Polymorphic,
+    Always,
+    Never,
+}
+
+/// How the type discriminator of a class is named and written.
+#[derive(Debug, Clone)]
+pub struct TypeNamePolicy {
+    pub emit: TypeEmit,
+    pub key: String,
+    /// The first matching prefix is stripped from the fully qualified name.
+    pub strip_prefixes: Vec<String>,
+    /// The first matching suffix is stripped.
+    pub strip_suffixes: Vec<String>,
+    /// Keep the namespace, e.g. `RPG.GameCore.` in front of the class name.
+    pub qualified: bool,
+}
+
+impl Default for TypeNamePolicy {
+    fn default() -> Self {
+        Self {
+            emit: TypeEmit::default(),
+            key: String::from("$type"),
+            strip_prefixes: Vec::new(),
+            strip_suffixes: vec![String::from("Inner")],
+            qualified: true,
+        }
+    }
+}
+
+impl TypeNamePolicy {
+    #[inline]
+    pub fn should_emit(&self, polymorphic: bool) -> bool {
+        match self.emit {
+            TypeEmit::Polymorphic => polymorphic,
+            TypeEmit::Always => true,
+            TypeEmit::Never => false,
+        }
+    }
+
+    pub fn display_name(&self, class_name: &str) -> String {
+        let mut name = self
+            .strip_prefixes
+            .iter()
+            .find_map(|prefix| class_name.strip_prefix(prefix.as_str()))
+            .unwrap_or(class_name);
+
+        if !self.qualified {
+            name = name.rsplit('.').next().unwrap_or(name);
+        }
+
+        self.strip_suffixes
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix.as_str()))
+            .unwrap_or(name)
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TypeNamePolicy;
+
+    #[test]
+    fn test_display_name() {
+        let policy = TypeNamePolicy::default();
+        assert_eq!(
+            "RPG.GameCore.TaskConfig",
+            policy.display_name("RPG.GameCore.TaskConfigInner")
+        );
+
+        let policy = TypeNamePolicy {
+            strip_prefixes: vec![String::from("RPG.")],
+            ..Default::default()
+        };
+        assert_eq!(
+            "GameCore.TaskConfig",
+            policy.display_name("RPG.GameCore.TaskConfigInner")
+        );
+
+        let policy = TypeNamePolicy {
+            qualified: false,
+            strip_suffixes: Vec::new(),
+            ..Default::default()
+        };
+        assert_eq!(
+            "TaskConfigInner",
+            policy.display_name("RPG.GameCore.TaskConfigInner")
+        );
+    }
+}
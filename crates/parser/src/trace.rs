@@ -0,0 +1,276 @@
+//! Byte-level record of a parse: which range every value came from, under
+//! which field path, so a file can be explained without `tracing::debug!`
+//! output.
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{DynamicParser, ValueKind};
+
+/// Longest decoded value kept on a span.
+const MAX_VALUE_LENGTH: usize = 80;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Trace {
+    /// Every value read, in the order they finished.
+    pub spans: Vec<TraceSpan>,
+    /// Innermost value that failed to parse, if any.
+    pub failure: Option<TraceFailure>,
+    #[serde(skip)]
+    path: Vec<String>,
+    #[serde(skip)]
+    depth: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TraceSpan {
+    pub start: u64,
+    pub end: u64,
+    /// Nesting level, containers are shallower than what they contain.
+    pub depth: usize,
+    pub path: String,
+    pub kind: String,
+    /// Decoded value, only on spans nothing else was read inside of.
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TraceFailure {
+    pub start: u64,
+    pub position: u64,
+    pub path: String,
+    pub kind: String,
+    pub error: String,
+}
+
+impl Trace {
+    /// Spans sorted by where they start, each container before its contents.
+    pub fn sorted_spans(&self) -> Vec<&TraceSpan> {
+        let mut spans = self.spans.iter().collect::<Vec<_>>();
+        spans.sort_by_key(|span| (span.start, span.depth));
+        spans
+    }
+
+    fn path_string(&self) -> String {
+        let mut out = String::new();
+        for segment in &self.path {
+            if !out.is_empty() && !segment.starts_with('[') {
+                out.push('.');
+            }
+            out.push_str(segment);
+        }
+        out
+    }
+}
+
+impl<'a> DynamicParser<'a> {
+    /// Records a [`Trace`] for every parse from now on.
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(Trace::default());
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    pub(crate) fn parse_traced(
+        &mut self,
+        kind: &ValueKind,
+        include_type: bool,
+    ) -> anyhow::Result<Value> {
+        let start = self.cursor.position();
+        let (path, first_child) = match self.trace.as_mut() {
+            Some(trace) => {
+                trace.depth += 1;
+                (trace.path_string(), trace.spans.len())
+            }
+            None => return self.parse_value(kind, include_type),
+        };
+
+        let result = self.parse_value(kind, include_type);
+
+        let end = self.cursor.position();
+        let Some(trace) = self.trace.as_mut() else {
+            return result;
+        };
+        let depth = trace.depth;
+        trace.depth -= 1;
+
+        let leaf = trace.spans.len() == first_child;
+        match &result {
+            Ok(value) => trace.spans.push(TraceSpan {
+                start,
+                end,
+                depth,
+                path,
+                kind: kind.to_string(),
+                value: leaf.then(|| summarize(value)),
+            }),
+            Err(err) => {
+                // containers of the failure are kept so its path reads in context
+                if !leaf {
+                    trace.spans.push(TraceSpan {
+                        start,
+                        end,
+                        depth,
+                        path: path.clone(),
+                        kind: kind.to_string(),
+                        value: None,
+                    });
+                }
+                if trace.failure.is_none() {
+                    trace.failure = Some(TraceFailure {
+                        start,
+                        position: end,
+                        path,
+                        kind: kind.to_string(),
+                        error: format!("{err:#}"),
+                    });
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Enters a field, element or entry. Not left again when parsing inside
+    /// fails, as the parse is abandoned anyway.
+    #[inline]
+    pub(crate) fn trace_enter(&mut self, segment: impl FnOnce() -> String) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.path.push(segment());
+        }
+    }
+
+    #[inline]
+    pub(crate) fn trace_leave(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.path.pop();
+        }
+    }
+
+    /// Records bytes read outside of a value, like lengths and exist flags,
+    /// from `start` up to the cursor.
+    #[inline]
+    pub(crate) fn trace_mark(&mut self, start: u64, kind: &str, value: impl FnOnce() -> String) {
+        let end = self.cursor.position();
+        if let Some(trace) = self.trace.as_mut() {
+            trace.spans.push(TraceSpan {
+                start,
+                end,
+                depth: trace.depth + 1,
+                path: trace.path_string(),
+                kind: kind.to_string(),
+                value: Some(value()),
+            });
+        }
+    }
+}
+
+fn summarize(value: &Value) -> String {
+    let value = match value {
+        Value::String(s) => format!("{s:?}"),
+        other => other.to_string(),
+    };
+
+    if value.chars().count() > MAX_VALUE_LENGTH {
+        let mut value = value.chars().take(MAX_VALUE_LENGTH).collect::<String>();
+        value.push('…');
+        value
+    } else {
+        value
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueKind::Primitive(name) | ValueKind::Class(name) => write!(f, "{name}"),
+            ValueKind::Array(item) => write!(f, "{item}[]"),
+            ValueKind::Dictionary(key, value) => write!(f, "Dictionary<{key}, {value}>"),
+            ValueKind::Nullable(value) => write!(f, "{value}?"),
+            ValueKind::FixedArray(item, count) => write!(f, "{item}[{count}]"),
+            ValueKind::Set(item) => write!(f, "HashSet<{item}>"),
+            ValueKind::Bytes => write!(f, "byte[]"),
+            ValueKind::Vector2 => write!(f, "Vector2"),
+            ValueKind::Vector3 => write!(f, "Vector3"),
+            ValueKind::Vector4 => write!(f, "Vector4"),
+            ValueKind::Quaternion => write!(f, "Quaternion"),
+            ValueKind::Color => write!(f, "Color"),
+            ValueKind::Other() => write!(f, "Other"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataDefine, DataField, DynamicParser, ValueKind};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_trace_spans() {
+        let types = HashMap::from([(
+            String::from("Row"),
+            DataDefine::Class {
+                skip_existflag_check: None,
+                fields: vec![
+                    DataField {
+                        field_name: String::from("ID"),
+                        data_type: ValueKind::Primitive(String::from("uint")),
+                    },
+                    DataField {
+                        field_name: String::from("Values"),
+                        data_type: ValueKind::Array(Box::new(ValueKind::Primitive(String::from(
+                            "string",
+                        )))),
+                    },
+                ],
+                interfaces: Vec::new(),
+            },
+        )]);
+
+        // both fields present, ID 7, then one string claiming 5 bytes of 1
+        let bytes = vec![3, 7, 2, 5, b'a'];
+        let mut parser = DynamicParser::new(&types, &bytes);
+        parser.enable_trace();
+        assert!(
+            parser
+                .parse(&ValueKind::Class(String::from("Row")), false)
+                .is_err()
+        );
+
+        let trace = parser.take_trace().unwrap();
+        let spans = trace
+            .sorted_spans()
+            .into_iter()
+            .map(|span| {
+                (
+                    span.start,
+                    span.end,
+                    span.path.as_str(),
+                    span.value.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 5, "", None),
+                (0, 1, "", Some("present: ID, Values")),
+                (1, 2, "ID", Some("7")),
+                (2, 5, "Values", None),
+                (2, 3, "Values", Some("1")),
+            ]
+        );
+
+        let failure = trace.failure.unwrap();
+        assert_eq!(failure.path, "Values[0]");
+        assert_eq!(failure.kind, "string");
+        assert_eq!(failure.start, 3);
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use varint_rs::VarintReader;
+
+use crate::{DataDefine, DynamicParser, ParseReport, ValueKind};
+
+/// Nested Typeindex chains deeper than this are treated as a schema loop.
+const MAX_RESOLVE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TypeindexProbe {
+    pub base: String,
+    pub typeindex: u64,
+    pub position: u64,
+    /// Candidates that parsed without error, best match first. Only a synced
+    /// one is ever taken.
+    pub candidates: Vec<ProbeCandidate>,
+}
+
+impl TypeindexProbe {
+    /// The candidate taken, if any stayed in sync.
+    pub fn best(&self) -> Option<&ProbeCandidate> {
+        self.candidates.first().filter(|c| c.synced)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ProbeCandidate {
+    pub type_name: String,
+    pub consumed: u64,
+    /// Parsing stopped at the end of the buffer or right before another known
+    /// discriminant of the same base.
+    pub synced: bool,
+}
+
+/// Follows descendants that are themselves Typeindex types through their
+/// default (`0`) entry, so abstract intermediate bases resolve to the
+/// concrete class that is actually serialized. The exporters follow them the
+/// same way, as no second discriminant is read.
+pub(crate) fn resolve_descendant<'t>(
+    types: &'t HashMap<String, DataDefine>,
+    mut kind: &'t ValueKind,
+) -> &'t ValueKind {
+    for _ in 0..MAX_RESOLVE_DEPTH {
+        let ValueKind::Class(name) = kind else {
+            break;
+        };
+
+        match types.get(name) {
+            Some(DataDefine::Typeindex { descendants, .. }) => match descendants.get(&0) {
+                Some(next) => kind = next,
+                None => break,
+            },
+            _ => break,
+        }
+    }
+
+    kind
+}
+
+impl<'a> DynamicParser<'a> {
+    pub(crate) fn probe_typeindex(&mut self, base: &str, typeindex: u64) -> anyhow::Result<Value> {
+        let start = self.cursor.position();
+        let known = self.known_typeindices(base);
+
+        tracing::debug!(
+            "DataDefine::Typeindex(cursor_pos: {start}) -> probing {base} for unknown typeindex {typeindex}"
+        );
+
+        // each candidate reports into a report of its own, only the one
+        // taken is kept
+        let report = std::mem::take(&mut self.report);
+        self.probing = true;
+        let mut candidates = Vec::new();
+        let mut best: Option<(Value, u64, ParseReport)> = None;
+
+        for type_name in self.candidate_types(base) {
+            self.cursor.set_position(start);
+
+            let kind = ValueKind::Class(type_name.clone());
+            let parsed = self.parse(&kind, true);
+            let candidate_report = std::mem::take(&mut self.report);
+            let Ok(value) = parsed else {
+                continue;
+            };
+
+            let end = self.cursor.position();
+            let synced = self.remaining() == 0
+                || self
+                    .cursor
+                    .read_u64_varint()
+                    .is_ok_and(|next| known.contains(&next));
+
+            let candidate = ProbeCandidate {
+                type_name,
+                consumed: end - start,
+                synced,
+            };
+            // a guess that loses sync right after isn't data, whatever it consumed
+            if synced
+                && candidates
+                    .iter()
+                    .filter(|c: &&ProbeCandidate| c.synced)
+                    .all(|c| candidate.consumed > c.consumed)
+            {
+                best = Some((value, end, candidate_report));
+            }
+            candidates.push(candidate);
+        }
+
+        self.probing = false;
+        self.report = report;
+        candidates.sort_by_key(|c| Reverse((c.synced, c.consumed)));
+
+        let probe = TypeindexProbe {
+            base: base.to_string(),
+            typeindex,
+            position: start,
+            candidates,
+        };
+
+        let Some((value, end, candidate_report)) = best else {
+            self.cursor.set_position(start);
+            self.report.typeindex_probes.push(probe);
+            return Err(anyhow::format_err!(
+                "typeindex not exist and no candidate stays in sync! base: {base} type index: {typeindex}"
+            ));
+        };
+
+        tracing::debug!(
+            "DataDefine::Typeindex(cursor_pos: {start}) -> {base} typeindex {typeindex} probed as {:?}",
+            probe.best().map(|c| &c.type_name)
+        );
+
+        self.cursor.set_position(end);
+        self.report.merge(candidate_report);
+        self.report.typeindex_probes.push(probe);
+
+        Ok(value)
+    }
+
+    /// Every discriminant any Typeindex over `base` already knows about.
+    fn known_typeindices(&self, base: &str) -> BTreeSet<u64> {
+        self.types
+            .values()
+            .filter_map(|define| match define {
+                DataDefine::Typeindex {
+                    base: other,
+                    descendants,
+                } if other == base => Some(descendants.keys().copied()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Concrete classes that could stand behind `base`: the known descendants
+    /// of every Typeindex over it, plus any class implementing it.
+    fn candidate_types(&self, base: &str) -> BTreeSet<String> {
+        let short_base = base.rsplit('.').next().unwrap_or(base);
+        let mut out = BTreeSet::new();
+
+        for (name, define) in self.types {
+            match define {
+                DataDefine::Typeindex {
+                    base: other,
+                    descendants,
+                } if other == base => {
+                    out.extend(descendants.values().filter_map(|kind| {
+                        match resolve_descendant(self.types, kind) {
+                            ValueKind::Class(name) => Some(name.clone()),
+                            _ => None,
+                        }
+                    }));
+                }
+                DataDefine::Class { interfaces, .. } | DataDefine::Struct { interfaces, .. }
+                    if interfaces
+                        .iter()
+                        .any(|i| i == base || i.rsplit('.').next() == Some(short_base)) =>
+                {
+                    out.insert(name.clone());
+                }
+                _ => {}
+            }
+        }
+
+        out.retain(|name| !matches!(self.types.get(name), Some(DataDefine::Typeindex { .. })));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataDefine, DataField, DynamicParser, ParseOptions, ValueKind};
+    use std::collections::{BTreeMap, HashMap};
+
+    fn class(fields: &[(&str, &str)], interfaces: &[&str]) -> DataDefine {
+        DataDefine::Struct {
+            fields: fields
+                .iter()
+                .map(|(name, kind)| DataField {
+                    field_name: name.to_string(),
+                    data_type: ValueKind::Primitive(kind.to_string()),
+                })
+                .collect(),
+            interfaces: interfaces.iter().map(|i| i.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_probe_unknown_typeindex() {
+        let types = HashMap::from([
+            (
+                "Task".to_string(),
+                DataDefine::Typeindex {
+                    base: "Task".to_string(),
+                    descendants: BTreeMap::from([(1, ValueKind::Class("TaskA".to_string()))]),
+                },
+            ),
+            ("TaskA".to_string(), class(&[("A", "int")], &["Task"])),
+            (
+                "TaskB".to_string(),
+                class(&[("B", "int"), ("C", "int")], &["Task"]),
+            ),
+            (
+                "TaskE".to_string(),
+                DataDefine::Struct {
+                    fields: vec![DataField {
+                        field_name: "E".to_string(),
+                        data_type: ValueKind::Class("Kind".to_string()),
+                    }],
+                    interfaces: vec!["Task".to_string()],
+                },
+            ),
+            (
+                "Kind".to_string(),
+                DataDefine::Enum(
+                    "int".to_string(),
+                    BTreeMap::from([("0".to_string(), "Zero".to_string())]),
+                ),
+            ),
+        ]);
+        let kind = ValueKind::Array(Box::new(ValueKind::Class("Task".to_string())));
+        // [typeindex 7 -> TaskB { 1, 2 }, typeindex 1 -> TaskA { 3 }]
+        let bytes = vec![4, 7, 2, 4, 1, 6];
+
+        let mut parser = DynamicParser::new(&types, &bytes);
+        assert!(parser.parse(&kind, false).is_err());
+
+        let options = ParseOptions {
+            probe_typeindex: true,
+            ..Default::default()
+        };
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        let parsed = parser.parse(&kind, false).unwrap();
+
+        assert_eq!(parsed[0]["$type"], "TaskB");
+        assert_eq!(parsed[1]["A"], 3);
+
+        let probe = &parser.report.typeindex_probes[0];
+        assert_eq!(7, probe.typeindex);
+        assert_eq!("TaskB", probe.best().unwrap().type_name);
+        assert!(probe.best().unwrap().synced);
+        // TaskE read an unknown Kind before losing sync, it isn't reported
+        assert_eq!(parser.report.enum_misses, 0);
+        assert!(parser.report.unknown_enums.is_empty());
+
+        // no candidate ends at a known discriminant
+        let bytes = vec![4, 7, 2, 4, 6, 6];
+        let mut parser = DynamicParser::with_options(&types, &bytes, &options);
+        assert!(parser.parse(&kind, false).is_err());
+        let probe = &parser.report.typeindex_probes[0];
+        assert!(!probe.candidates.is_empty());
+        assert!(probe.best().is_none());
+        assert_eq!(parser.report.enum_misses, 0);
+    }
+}
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use common::{downloader, glob, source::AssetSource};
+use std::path::Path;
+use tg_asset_meta::asset_index::IndexKind;
+
+/// Lists the entries of another index kind matching `filter`, or downloads
+/// them into `download` when given.
+pub fn assets(
+    source: &dyn AssetSource,
+    kind: IndexKind,
+    filter: Option<&str>,
+    download: Option<&Path>,
+    json: bool,
+) -> Result<()> {
+    let (mini_asset, index) = downloader::fetch_asset_index(source, kind)?;
+    let entries = index
+        .entries
+        .iter()
+        .filter(|entry| filter.is_none_or(|filter| glob::matches(filter, &entry.name)))
+        .collect::<Vec<_>>();
+    tracing::info!(
+        "{} {} index of revision {} lists {} files, {} selected",
+        source.describe(),
+        kind.name(),
+        mini_asset.revision_id,
+        index.entries.len(),
+        entries.len()
+    );
+
+    if let Some(download) = download {
+        let failed = downloader::download_asset_files(source, kind, &entries, download);
+        if failed > 0 {
+            anyhow::bail!("{failed} of {} files failed to download", entries.len());
+        }
+        tracing::info!("Downloaded {} files into {download:?}", entries.len());
+    } else if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        println!("{:>12}  {:<32}  {:<5}  NAME", "SIZE", "MD5", "PATCH");
+        for entry in entries {
+            println!(
+                "{:>12}  {:<32}  {:<5}  {}",
+                entry.size,
+                entry.md5.as_deref().unwrap_or("-"),
+                entry.patch,
+                entry.name
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use anyhow::{Context as _, Result};
+use common::{downloader, source::AssetSource};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+use tg_asset_meta::design_index::DesignIndex;
+
+/// How an entry of one platform's index relates to the other's.
+#[derive(Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PlatformComparison {
+    pub revisions: (u32, u32),
+    pub blocks: (usize, usize),
+    pub shared_blocks: usize,
+    /// Same place of the same block, so the same data
+    pub identical: usize,
+    /// In another block but of the same size, the data may still differ
+    pub same_size: BTreeSet<i32>,
+    pub size_differs: BTreeMap<i32, (u32, u32)>,
+    pub only_in_a: BTreeSet<i32>,
+    pub only_in_b: BTreeSet<i32>,
+}
+
+pub fn compare_indices(a: &DesignIndex, b: &DesignIndex) -> PlatformComparison {
+    let blocks_a = a
+        .file_list
+        .iter()
+        .map(|file_entry| file_entry.file_byte_name.as_str())
+        .collect::<BTreeSet<_>>();
+    let blocks_b = b
+        .file_list
+        .iter()
+        .map(|file_entry| file_entry.file_byte_name.as_str())
+        .collect::<BTreeSet<_>>();
+
+    let mut comparison = PlatformComparison {
+        blocks: (blocks_a.len(), blocks_b.len()),
+        shared_blocks: blocks_a.intersection(&blocks_b).count(),
+        ..Default::default()
+    };
+
+    let entries_a = downloader::entry_keys(a);
+    let entries_b = downloader::entry_keys(b);
+    for (name_hash, entry_a) in &entries_a {
+        match entries_b.get(name_hash) {
+            None => {
+                comparison.only_in_a.insert(*name_hash);
+            }
+            Some(entry_b) if entry_a == entry_b => comparison.identical += 1,
+            Some(entry_b) if entry_a.2 == entry_b.2 => {
+                comparison.same_size.insert(*name_hash);
+            }
+            Some(entry_b) => {
+                comparison
+                    .size_differs
+                    .insert(*name_hash, (entry_a.2, entry_b.2));
+            }
+        }
+    }
+    comparison.only_in_b = entries_b
+        .keys()
+        .filter(|name_hash| !entries_a.contains_key(name_hash))
+        .copied()
+        .collect();
+
+    comparison
+}
+
+/// Compares the design index of two platforms, printing a summary and
+/// optionally writing the whole comparison as JSON.
+pub fn compare_platforms(
+    a: &dyn AssetSource,
+    b: &dyn AssetSource,
+    output: Option<&Path>,
+) -> Result<()> {
+    let mini_a =
+        downloader::fetch_mini_asset(a).context(format!("Failed to read {}", a.describe()))?;
+    let mini_b =
+        downloader::fetch_mini_asset(b).context(format!("Failed to read {}", b.describe()))?;
+    if mini_a.revision_id != mini_b.revision_id {
+        tracing::warn!(
+            "Comparing different revisions, {} and {}",
+            mini_a.revision_id,
+            mini_b.revision_id
+        );
+    }
+
+    let index_a = downloader::fetch_design_index(a, None)?;
+    let index_b = downloader::fetch_design_index(b, None)?;
+    let mut comparison = compare_indices(&index_a, &index_b);
+    comparison.revisions = (mini_a.revision_id, mini_b.revision_id);
+
+    tracing::info!("A: {}, B: {}", a.describe(), b.describe());
+    tracing::info!(
+        "{} and {} blocks, {} shared",
+        comparison.blocks.0,
+        comparison.blocks.1,
+        comparison.shared_blocks
+    );
+    tracing::info!(
+        "{} entries identical, {} same size, {} differ in size, {} only in A, {} only in B",
+        comparison.identical,
+        comparison.same_size.len(),
+        comparison.size_differs.len(),
+        comparison.only_in_a.len(),
+        comparison.only_in_b.len()
+    );
+
+    if let Some(output) = output {
+        fs::write(output, serde_json::to_string_pretty(&comparison)?)
+            .context(format!("Failed to write {output:?}"))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare_indices;
+    use crate::actions::tests::design_index as index;
+    #[test]
+    fn test_compare_indices() {
+        let windows = index(&[("a", &[(1, 0, 4), (2, 4, 4)]), ("b", &[(3, 0, 4)])]);
+        let android = index(&[
+            ("a", &[(1, 0, 4), (2, 4, 4)]),
+            ("c", &[(3, 0, 4), (4, 4, 8)]),
+        ]);
+
+        let comparison = compare_indices(&windows, &android);
+        assert_eq!(comparison.blocks, (2, 2));
+        assert_eq!(comparison.shared_blocks, 1);
+        assert_eq!(comparison.identical, 2);
+        assert_eq!(comparison.same_size.into_iter().collect::<Vec<_>>(), [3]);
+        assert!(comparison.size_differs.is_empty());
+        assert!(comparison.only_in_a.is_empty());
+        assert_eq!(comparison.only_in_b.into_iter().collect::<Vec<_>>(), [4]);
+    }
+}
@@ -1,4 +1,4 @@
-use super::{ConfigManifest, parse_config};
+use super::{ConfigManifest, parse_parent_config};
 use crate::{COUNTER_CONFIGS, parse_and_count};
 use anyhow::Result;
 use dashmap::DashSet;
@@ -83,14 +83,16 @@ fn parse_mission_info(
     let sub_mission_paths = DashSet::new();
 
     paths.par_iter().for_each(|path| {
-        if let Ok(config) = parse_config(
+        if let Ok((config, parsed)) = parse_parent_config(
             path,
             "RPG.GameCore.MainMissionInfoConfig",
             assets,
             types,
             out_folder,
         ) {
-            COUNTER_CONFIGS.fetch_add(1, Ordering::Relaxed);
+            if parsed {
+                COUNTER_CONFIGS.fetch_add(1, Ordering::Relaxed);
+            }
             if let Some(Value::Array(sub_mission_list)) = config.get("SubMissionList") {
                 for sub_mission in sub_mission_list {
                     let Some(Value::String(json_path)) = sub_mission.get("MissionJsonPath") else {
@@ -8,8 +8,14 @@ use std::{
     fs, panic,
     path::{Path, PathBuf},
 };
+use tg_asset_meta::baked_header::{BakedHeader, ConfigHeader};
 use tg_parser::{DataDefine, DynamicParser, ValueKind};
 
+use super::{
+    log_report, parse_options,
+    report::{self, FileReport},
+};
+
 mod adventure_ability;
 mod adventure_modifier;
 mod complex_skill_ai_global;
@@ -81,9 +87,28 @@ fn parse_config(
         anyhow::anyhow!("Asset not found")
     })?;
 
+    let (header, bytes) = match ConfigHeader::split(bytes) {
+        Ok((header, bytes)) => (header, bytes.to_vec()),
+        Err(err) => {
+            tracing::error!("{json_path}: {err}, not parsing it");
+            let mut file_report = FileReport::new(type_name, None);
+            file_report.error = Some(err.to_string());
+            report::record(json_path, file_report);
+            return Ok(json!({}));
+        }
+    };
+
     match panic::catch_unwind(|| {
-        let mut parser = DynamicParser::new(types, bytes);
-        parser.parse(&ValueKind::Class(type_name.to_string()), false)
+        let mut parser = DynamicParser::with_options(types, &bytes, parse_options());
+        let result = parser.parse(&ValueKind::Class(type_name.to_string()), false);
+        log_report(json_path, &parser.report);
+
+        let mut file_report = FileReport::new(type_name, header.map(BakedHeader::Config))
+            .with_parse_report(&parser.report);
+        file_report.error = result.as_ref().err().map(|err| err.to_string());
+        report::record(json_path, file_report);
+
+        result
     }) {
         Ok(Ok(parsed)) => {
             let out_folder = out_folder.join(folder_path);
@@ -100,6 +125,25 @@ fn parse_config(
     Ok(json!({}))
 }
 
+/// Parses a config listing further configs to parse. An update run only has
+/// the assets that changed, so an unchanged one is read back from its output
+/// of the previous run instead. The flag is whether it was parsed this run.
+fn parse_parent_config(
+    json_path: &str,
+    type_name: &str,
+    assets: &HashMap<i32, Vec<u8>>,
+    types: &HashMap<String, DataDefine>,
+    out_folder: &Path,
+) -> Result<(Value, bool)> {
+    match parse_config(json_path, type_name, assets, types, out_folder) {
+        Ok(config) => Ok((config, true)),
+        Err(err) => match fs::read(out_folder.join(json_path)) {
+            Ok(previous) => Ok((serde_json::from_slice(&previous)?, false)),
+            Err(_) => Err(err),
+        },
+    }
+}
+
 type ParseFn =
     fn(&HashMap<i32, Vec<u8>>, &HashMap<String, DataDefine>, &Path, &ConfigManifest) -> Result<()>;
 
@@ -1,5 +1,5 @@
 use super::ConfigManifest;
-use crate::{COUNTER_CONFIGS, actions::config::parse_config, parse_and_count};
+use crate::{COUNTER_CONFIGS, actions::config::parse_parent_config, parse_and_count};
 use anyhow::Result;
 use dashmap::DashSet;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
@@ -38,14 +38,16 @@ pub fn parse(
     let option_paths = DashSet::new();
 
     paths.par_iter().for_each(|path| {
-        if let Ok(config) = parse_config(
+        if let Ok((config, parsed)) = parse_parent_config(
             path,
             "RPG.GameCore.RogueNPCConfig",
             assets,
             types,
             out_folder,
         ) {
-            COUNTER_CONFIGS.fetch_add(1, Ordering::Relaxed);
+            if parsed {
+                COUNTER_CONFIGS.fetch_add(1, Ordering::Relaxed);
+            }
             if let Some(Value::Array(dialogue_list)) = config.get("DialogueList") {
                 for dialogue in dialogue_list {
                     if let Some(Value::String(json_path)) = dialogue.get("DialoguePath") {
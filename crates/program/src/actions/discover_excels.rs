@@ -0,0 +1,292 @@
+use anyhow::Result;
+use common::{downloader, hash, source::AssetSource};
+use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+use tg_asset_meta::baked_header::ExcelHeader;
+use tg_parser::{DataDefine, DynamicParser, ValueKind, dump_cs::excel_path_candidates};
+
+use super::{parse_options, textmap::TEXTMAP_PATHS};
+
+/// A candidate path whose hash is in the index, for one row type.
+struct Candidate<'a> {
+    type_name: &'a str,
+    path: String,
+    /// Position in the type's candidate list, lower is more likely.
+    rank: usize,
+}
+
+/// Matches every `*Row` type in `types` against the design index of `source`
+/// by the hash of its candidate ExcelOutput paths, then downloads only the
+/// blocks holding a match and keeps the ones that parse every byte. Writes
+/// `excel_paths.json` and `unmatched_hashes.json` into `output_dir`, the
+/// latter only listing the unclaimed assets that have an Excel header, which
+/// takes downloading them.
+pub fn discover_excels(
+    source: &dyn AssetSource,
+    types: &HashMap<String, DataDefine>,
+    output_dir: &Path,
+) -> Result<()> {
+    let design_index = downloader::fetch_design_index(source, None)?;
+    let indexed = design_index
+        .file_list
+        .iter()
+        .flat_map(downloader::asset_hashes)
+        .collect::<HashSet<_>>();
+
+    let mut candidates = Vec::new();
+    for (type_name, define) in types {
+        if !type_name.ends_with("Row") || !matches!(define, DataDefine::Class { .. }) {
+            continue;
+        }
+
+        for (rank, path) in excel_path_candidates(type_name).into_iter().enumerate() {
+            if indexed.contains(&hash::get_32bit_hash_const(&path)) {
+                candidates.push(Candidate {
+                    type_name,
+                    path,
+                    rank,
+                });
+            }
+        }
+    }
+
+    tracing::info!(
+        "{} candidate paths found in the index, confirming...",
+        candidates.len()
+    );
+
+    // an empty filter would download everything
+    let assets = if candidates.is_empty() {
+        HashMap::new()
+    } else {
+        let filter_hashes = candidates
+            .iter()
+            .map(|candidate| hash::get_32bit_hash_const(&candidate.path))
+            .collect::<Vec<_>>();
+        downloader::download_design_files(&[(source, &design_index, None)], &filter_hashes).assets
+    };
+
+    let confirmed = candidates
+        .into_par_iter()
+        .filter(|candidate| {
+            let Some(bytes) = assets.get(&hash::get_32bit_hash_const(&candidate.path)) else {
+                tracing::warn!("{} couldn't be downloaded", candidate.path);
+                return false;
+            };
+            match strict_parse(types, candidate.type_name, bytes) {
+                Ok(()) => true,
+                Err(err) => {
+                    tracing::warn!(
+                        "{} matched {} by hash but failed to parse: {err}",
+                        candidate.path,
+                        candidate.type_name
+                    );
+                    false
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // a path every byte of which parses as more than one type goes to the
+    // type it's the most likely candidate of
+    let mut by_path: BTreeMap<&str, &Candidate> = BTreeMap::new();
+    for candidate in &confirmed {
+        let best = by_path.entry(&candidate.path).or_insert(candidate);
+        if (candidate.rank, candidate.type_name) < (best.rank, best.type_name) {
+            tracing::info!(
+                "{} parses as both {} and {}, keeping {}",
+                candidate.path,
+                best.type_name,
+                candidate.type_name,
+                candidate.type_name
+            );
+            *best = candidate;
+        }
+    }
+
+    let mut excel_paths: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (path, candidate) in &by_path {
+        excel_paths
+            .entry(candidate.type_name)
+            .or_default()
+            .push(path);
+    }
+
+    let claimed = by_path
+        .keys()
+        .map(|path| hash::get_32bit_hash_const(path))
+        .chain(TEXTMAP_PATHS.iter().map(|(_, hash)| *hash))
+        .chain([hash::get_32bit_hash_const(
+            "BakedConfig/ConfigManifest.json",
+        )])
+        .collect::<BTreeSet<_>>();
+    let unclaimed = indexed
+        .iter()
+        .filter(|hash| !claimed.contains(hash))
+        .copied()
+        .collect::<Vec<_>>();
+    // configs, levels and the like are unclaimed too but aren't missing Excels
+    let unmatched = if unclaimed.is_empty() {
+        BTreeSet::new()
+    } else {
+        tracing::info!(
+            "Checking the headers of {} unclaimed assets...",
+            unclaimed.len()
+        );
+        downloader::download_design_files(&[(source, &design_index, None)], &unclaimed)
+            .assets
+            .into_iter()
+            .filter(|(_, bytes)| is_plausible_excel(bytes))
+            .map(|(hash, _)| hash)
+            .collect::<BTreeSet<_>>()
+    };
+
+    fs::create_dir_all(output_dir)?;
+    fs::write(
+        output_dir.join("excel_paths.json"),
+        serde_json::to_string_pretty(&excel_paths)?,
+    )?;
+    fs::write(
+        output_dir.join("unmatched_hashes.json"),
+        serde_json::to_string_pretty(&unmatched)?,
+    )?;
+
+    tracing::info!(
+        "Discovered {} Excels for {} types, {} more assets look like Excels",
+        by_path.len(),
+        excel_paths.len(),
+        unmatched.len()
+    );
+
+    Ok(())
+}
+
+/// Whether `bytes` starts with an Excel header and has rows, or is nothing
+/// but an empty table.
+fn is_plausible_excel(bytes: &[u8]) -> bool {
+    match ExcelHeader::split(bytes) {
+        Ok((header, rows)) => header.row_count > 0 || rows.len() <= 1,
+        Err(_) => false,
+    }
+}
+
+/// Parses `bytes` as an array of `type_name` rows, failing unless every byte
+/// is consumed.
+fn strict_parse(types: &HashMap<String, DataDefine>, type_name: &str, bytes: &[u8]) -> Result<()> {
+    let (_, bytes) = ExcelHeader::split(bytes)?;
+    let bytes = bytes.to_vec();
+    let kind = ValueKind::Array(Box::new(ValueKind::Class(type_name.to_string())));
+
+    let mut parser = DynamicParser::with_options(types, &bytes, parse_options());
+    match panic::catch_unwind(AssertUnwindSafe(|| parser.parse(&kind, false))) {
+        Ok(Ok(_)) if parser.remaining() == 0 => Ok(()),
+        Ok(Ok(_)) => Err(anyhow::format_err!("{} trailing bytes", parser.remaining())),
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err(anyhow::format_err!("parser panicked")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::discover_excels;
+    use common::{
+        hash::get_32bit_hash_const,
+        source::{AssetSource, MemorySource},
+    };
+    use std::{collections::HashMap, fs, sync::Mutex};
+    use tg_asset_meta::repack::{RepackOptions, repack};
+    use tg_parser::{DataDefine, DataField, ValueKind};
+
+    /// Remembers the names read through it.
+    struct Recording {
+        inner: MemorySource,
+        reads: Mutex<Vec<String>>,
+    }
+
+    impl AssetSource for Recording {
+        fn read(&self, name: &str) -> anyhow::Result<Vec<u8>> {
+            self.reads.lock().unwrap().push(name.to_string());
+            self.inner.read(name)
+        }
+
+        fn describe(&self) -> String {
+            self.inner.describe()
+        }
+    }
+
+    #[test]
+    fn test_discover_excels() {
+        let path = "BakedConfig/ExcelOutput/AvatarConfig.bytes";
+        // version 0, one row of ID 5
+        // an Excel of no known type, and a config with exist flags first
+        let assets = HashMap::from([
+            (get_32bit_hash_const(path), vec![0, 2, 1, 10]),
+            (1, vec![0, 2, 1, 4]),
+            (2, vec![3, 1, 2, 3]),
+        ]);
+        let repacked = repack(
+            &assets,
+            &RepackOptions {
+                max_block_size: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let source = Recording {
+            inner: MemorySource {
+                files: repacked.files().unwrap().into_iter().collect(),
+            },
+            reads: Mutex::new(Vec::new()),
+        };
+
+        let types = HashMap::from([(
+            String::from("RPG.GameCore.AvatarConfigRow"),
+            DataDefine::Class {
+                skip_existflag_check: None,
+                fields: vec![DataField {
+                    field_name: String::from("ID"),
+                    data_type: ValueKind::Primitive(String::from("int")),
+                }],
+                interfaces: Vec::new(),
+            },
+        )]);
+        let dir = std::env::temp_dir().join(format!("tg-discover-{}", std::process::id()));
+        discover_excels(&source, &types, &dir).unwrap();
+
+        let excel_paths: serde_json::Value =
+            serde_json::from_slice(&fs::read(dir.join("excel_paths.json")).unwrap()).unwrap();
+        let unmatched: Vec<i32> =
+            serde_json::from_slice(&fs::read(dir.join("unmatched_hashes.json")).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            excel_paths,
+            serde_json::json!({ "RPG.GameCore.AvatarConfigRow": [path] })
+        );
+        assert_eq!(unmatched, [1]);
+
+        // only the block of the match is downloaded to confirm it, the
+        // others only afterwards for their headers
+        let block_of = |name_hash| {
+            let file_entry = repacked
+                .design_index
+                .file_list
+                .iter()
+                .find(|file_entry| file_entry.name_hash == name_hash)
+                .unwrap();
+            format!("{}.bytes", file_entry.file_byte_name)
+        };
+        let reads = source.reads.lock().unwrap();
+        assert_eq!(reads.len(), 5);
+        let match_read = reads
+            .iter()
+            .position(|name| *name == block_of(get_32bit_hash_const(path)))
+            .unwrap();
+        let other_read = reads.iter().position(|name| *name == block_of(1)).unwrap();
+        assert!(match_read < other_read);
+    }
+}
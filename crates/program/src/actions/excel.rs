@@ -2,10 +2,16 @@ use anyhow::Context;
 use common::hash;
 use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
 use std::{collections::HashMap, fs, path::Path, sync::atomic::Ordering};
+use tg_asset_meta::baked_header::{BakedHeader, ExcelHeader};
 use tg_parser::{DataDefine, DynamicParser, ValueKind};
 
 use crate::COUNTER_EXCELS;
 
+use super::{
+    log_report, parse_options,
+    report::{self, FileReport},
+};
+
 pub fn parse_all_excels(
     assets: &HashMap<i32, Vec<u8>>,
     types: &HashMap<String, DataDefine>,
@@ -27,16 +33,32 @@ pub fn parse_all_excels(
                 continue;
             };
 
-            // Skip empty first byte
-            let bytes = if !bytes.is_empty() && bytes[0] == 0 {
-                &bytes[1..]
-            } else {
-                bytes
-            }
-            .to_vec();
+            let (header, bytes) = match ExcelHeader::split(bytes) {
+                Ok((header, rows)) => (header, rows.to_vec()),
+                Err(err) => {
+                    tracing::error!("{path}: {err}, not parsing it");
+                    let mut file_report = FileReport::new(type_name, None);
+                    file_report.error = Some(err.to_string());
+                    report::record(path, file_report);
+                    continue;
+                }
+            };
+
+            let mut parser = DynamicParser::with_options(types, &bytes, parse_options());
+            let result = parser.parse(&kind, false);
+            log_report(path, &parser.report);
+
+            let mut file_report = FileReport::new(type_name, Some(BakedHeader::Excel(header)))
+                .with_parse_report(&parser.report);
+            file_report.rows = result
+                .as_ref()
+                .ok()
+                .and_then(|v| v.as_array())
+                .map(Vec::len);
+            file_report.error = result.as_ref().err().map(|err| err.to_string());
+            report::record(path, file_report);
 
-            let mut parser = DynamicParser::new(types, &bytes);
-            match parser.parse(&kind, false) {
+            match result {
                 Ok(parsed) => {
                     let file_name = path.split("/").last().unwrap().replace(".bytes", ".json");
                     let file_out = if file_name.starts_with("Textmap") {
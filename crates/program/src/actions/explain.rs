@@ -0,0 +1,142 @@
+use anyhow::{Context as _, Result};
+use std::{collections::HashMap, fs, path::Path};
+use tg_asset_meta::baked_header::{ConfigHeader, ExcelHeader};
+use tg_parser::{DataDefine, DynamicParser, ParseOptions, TraceSpan, ValueKind};
+
+/// Bytes shown on a single hexdump line.
+const ROW_WIDTH: usize = 16;
+
+/// Parses `bytes` as `root_type` and prints every byte range with the field it
+/// was read into, stopping at the first error.
+pub fn explain(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    bytes: &[u8],
+    root_type: &str,
+    array: bool,
+    trace_json: Option<&Path>,
+) -> Result<()> {
+    let split = if array {
+        ExcelHeader::split(bytes).map(|(header, data)| (Some(format!("{header:?}")), data))
+    } else {
+        ConfigHeader::split(bytes).map(|(header, data)| (header.map(|h| format!("{h:?}")), data))
+    };
+    // an odd header is what this is for, so it's read raw rather than given up on
+    let (header, data) = split.unwrap_or_else(|err| {
+        println!("Header: {err}, reading from offset 0");
+        (None, bytes)
+    });
+    let base = (bytes.len() - data.len()) as u64;
+    if let Some(header) = header {
+        println!("{:08x}  {:<48}  {header}", 0, hex(&bytes[..base as usize]));
+    }
+
+    let class = ValueKind::Class(root_type.to_string());
+    let kind = if array {
+        ValueKind::Array(Box::new(class))
+    } else {
+        class
+    };
+
+    let data = data.to_vec();
+    let mut parser = DynamicParser::with_options(types, &data, options);
+    parser.enable_trace();
+    let result = parser.parse(&kind, false);
+    let consumed = parser.cursor.position();
+    let trace = parser.take_trace().context("trace was not recorded")?;
+
+    for span in trace.sorted_spans() {
+        print_span(&data, base, span);
+    }
+
+    println!();
+    match (&result, &trace.failure) {
+        (Err(_), Some(failure)) => {
+            let path = if failure.path.is_empty() {
+                "<root>"
+            } else {
+                &failure.path
+            };
+            println!(
+                "Failed at {:#010x} in {path} ({}), started at {:#010x}: {}",
+                base + failure.position,
+                failure.kind,
+                base + failure.start,
+                failure.error
+            );
+            print_failure(&data, base, failure.start, failure.position);
+        }
+        (Err(err), None) => println!("Failed: {err:#}"),
+        (Ok(_), _) if consumed < data.len() as u64 => println!(
+            "Parsed, {} trailing bytes left from {:#010x}",
+            data.len() as u64 - consumed,
+            base + consumed
+        ),
+        (Ok(_), _) => println!("Parsed all {} bytes", data.len()),
+    }
+
+    if let Some(path) = trace_json {
+        fs::write(path, serde_json::to_string_pretty(&trace)?)
+            .context(format!("Failed to write {path:?}"))?;
+    }
+
+    Ok(())
+}
+
+fn print_span(data: &[u8], base: u64, span: &TraceSpan) {
+    let indent = "  ".repeat(span.depth.saturating_sub(1));
+    let label = match (span.path.is_empty(), &span.value) {
+        (true, None) => span.kind.clone(),
+        (true, Some(value)) => format!("{} = {value}", span.kind),
+        (false, None) => format!("{}: {}", span.path, span.kind),
+        (false, Some(value)) => format!("{}: {} = {value}", span.path, span.kind),
+    };
+
+    // only leaves own their bytes, containers are just headings
+    let bytes = match span.value {
+        Some(_) => {
+            let range = &data[span.start as usize..span.end as usize];
+            let mut bytes = hex(&range[..range.len().min(ROW_WIDTH)]);
+            if range.len() > ROW_WIDTH {
+                bytes.push_str(" …");
+            }
+            bytes
+        }
+        None => String::new(),
+    };
+
+    println!("{:08x}  {bytes:<48}  {indent}{label}", base + span.start);
+}
+
+/// Hexdump rows around the failing value, its bytes bracketed.
+fn print_failure(data: &[u8], base: u64, start: u64, position: u64) {
+    let start = start as usize;
+    let end = (position as usize).max(start + 1);
+    let first_row = start.saturating_sub(ROW_WIDTH) / ROW_WIDTH * ROW_WIDTH;
+    let last_row = (end + ROW_WIDTH).min(data.len());
+
+    for row in (first_row..last_row).step_by(ROW_WIDTH) {
+        let line = (row..(row + ROW_WIDTH).min(data.len()))
+            .map(|i| {
+                if (start..end).contains(&i) {
+                    format!("[{:02x}]", data[i])
+                } else {
+                    format!(" {:02x} ", data[i])
+                }
+            })
+            .collect::<String>();
+        println!("{:08x} {line}", base as usize + row);
+    }
+
+    if end > data.len() {
+        println!("(buffer ends at {:#010x})", base as usize + data.len());
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,35 @@
+use crate::cli::SchemaFormat;
+use anyhow::{Context as _, Result};
+use std::{collections::HashMap, fs, path::Path};
+use tg_parser::{DataDefine, ParseOptions, export};
+
+pub fn export(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    root_type: &str,
+    output: &Path,
+    format: Option<SchemaFormat>,
+) -> Result<()> {
+    let format = match format {
+        Some(format) => format,
+        None => match output.extension().and_then(|ext| ext.to_str()) {
+            Some("hexpat" | "pat") => SchemaFormat::Hexpat,
+            Some("ksy") => SchemaFormat::Ksy,
+            _ => anyhow::bail!("can't tell the format of {output:?}, pass --format"),
+        },
+    };
+
+    let schema = match format {
+        SchemaFormat::Hexpat => export::to_hexpat(types, options, root_type)?,
+        SchemaFormat::Ksy => export::to_ksy(types, options, root_type)?,
+    };
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, schema).with_context(|| format!("Failed to write {output:?}"))?;
+
+    tracing::info!("Exported {root_type} to {output:?}");
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use super::known_paths::KnownPaths;
+use anyhow::{Context as _, Result};
+use common::{downloader, hash, source::AssetSource};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Name hashes picked by selectors, with their path when known. A selector
+/// is a glob over the known paths when it has `*` or `?`, a raw 32-bit hash
+/// when it parses as one, or else a logical path.
+pub fn resolve(selectors: &[String], known: &KnownPaths) -> Result<BTreeMap<i32, Option<String>>> {
+    let mut selected = BTreeMap::new();
+    for selector in selectors {
+        if selector.contains(['*', '?']) {
+            let before = selected.len();
+            for (name_hash, path) in known.matching(selector) {
+                selected.insert(name_hash, Some(path.to_string()));
+            }
+            if selected.len() == before {
+                anyhow::bail!("{selector} matches no known path, see --paths");
+            }
+        } else if let Ok(name_hash) = selector.parse::<i32>() {
+            selected.insert(name_hash, known.get(name_hash).map(str::to_string));
+        } else {
+            selected.insert(
+                hash::get_32bit_hash_const(selector),
+                Some(selector.to_string()),
+            );
+        }
+    }
+    Ok(selected)
+}
+
+/// Where an asset is written under the output folder: its path when known
+/// and safe to use, its hash otherwise.
+pub fn output_path(name_hash: i32, path: Option<&str>) -> PathBuf {
+    path.map(Path::new)
+        .filter(|path| {
+            path.components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        })
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("{name_hash}.bytes")))
+}
+
+/// Writes the raw bytes of every selected asset, sliced out of its block.
+/// The ConfigManifest is written as the JSON it is.
+pub fn extract(
+    source: &dyn AssetSource,
+    selected: &BTreeMap<i32, Option<String>>,
+    out_folder: &Path,
+) -> Result<()> {
+    let mut assets =
+        downloader::download_all_design_data(source, None, selected.keys().copied().collect())?;
+
+    let mut missing = 0;
+    for (name_hash, path) in selected {
+        let Some(data) = assets.remove(name_hash) else {
+            tracing::error!(
+                "{} ({name_hash}) not found in design index",
+                path.as_deref().unwrap_or("<unknown>")
+            );
+            missing += 1;
+            continue;
+        };
+
+        let out_path = out_folder.join(output_path(*name_hash, path.as_deref()));
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&out_path, &data).context(format!("Failed to write {out_path:?}"))?;
+        tracing::info!("{} bytes to {out_path:?}", data.len());
+    }
+
+    if missing > 0 {
+        anyhow::bail!("{missing} of {} assets not found", selected.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{output_path, resolve};
+    use crate::actions::known_paths::KnownPaths;
+    use common::hash::get_32bit_hash_const;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_resolve() {
+        let file = std::env::temp_dir().join(format!("tg-extract-{}.txt", std::process::id()));
+        std::fs::write(
+            &file,
+            "BakedConfig/ExcelOutput/AvatarConfig.bytes\nBakedConfig/ExcelOutput/ItemConfig.bytes\n",
+        )
+        .unwrap();
+        let known = KnownPaths::load(&[&file]).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let avatar = get_32bit_hash_const("BakedConfig/ExcelOutput/AvatarConfig.bytes");
+        let selected = resolve(
+            &[
+                String::from("BakedConfig/ExcelOutput/*Config.bytes"),
+                String::from("BakedConfig/Config/Level.bytes"),
+                avatar.to_string(),
+                String::from("42"),
+            ],
+            &known,
+        )
+        .unwrap();
+        assert_eq!(selected.len(), 4);
+        assert_eq!(
+            selected[&avatar].as_deref(),
+            Some("BakedConfig/ExcelOutput/AvatarConfig.bytes")
+        );
+        assert_eq!(selected[&42], None);
+        assert!(resolve(&[String::from("Nothing/*")], &known).is_err());
+
+        assert_eq!(output_path(42, None), PathBuf::from("42.bytes"));
+        assert_eq!(
+            output_path(42, Some("../escape.bytes")),
+            PathBuf::from("42.bytes")
+        );
+        assert_eq!(
+            output_path(42, Some("BakedConfig/a.bytes")),
+            PathBuf::from("BakedConfig/a.bytes")
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::{Context as _, Result};
+use std::{fs, path::Path};
+use tg_parser::dump_cs::{self, SchemaHints};
+
+pub fn gen_schema(dump_cs: &Path, output_dir: &Path, hints: Option<&Path>) -> Result<()> {
+    let hints: SchemaHints = match hints {
+        Some(path) => serde_json::from_slice(&fs::read(path).context("Failed to read hints")?)
+            .context("Failed to parse hints")?,
+        None => SchemaHints::default(),
+    };
+
+    let dump = fs::read_to_string(dump_cs).context("Failed to read dump.cs")?;
+    let schema = dump_cs::generate_schema(&dump, &hints);
+
+    for type_name in &schema.unresolved {
+        tracing::warn!("unresolved field type: {type_name}");
+    }
+
+    fs::create_dir_all(output_dir)?;
+    fs::write(
+        output_dir.join("data.json"),
+        serde_json::to_string_pretty(&schema.types)?,
+    )?;
+    fs::write(
+        output_dir.join("excel_paths.json"),
+        serde_json::to_string_pretty(&schema.excel_paths)?,
+    )?;
+
+    tracing::info!(
+        "Generated {} types and {} Excel row types into {output_dir:?}",
+        schema.types.len(),
+        schema.excel_paths.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use tg_parser::{DataDefine, ParseOptions, guess_type};
+
+pub fn print_guesses(
+    types: &HashMap<String, DataDefine>,
+    options: &ParseOptions,
+    bytes: &[u8],
+    top: usize,
+) -> Result<()> {
+    tracing::info!("Guessing type of {} bytes...", bytes.len());
+
+    let guesses = guess_type(types, options, bytes);
+
+    println!(
+        "{:>7}  {:<13} {:>15}  {:>9}  {:>7}  {:>7}  TYPE",
+        "SCORE", "HEADER", "CONSUMED", "ENUMS", "BADLEN", "EMPTY"
+    );
+    for guess in guesses.iter().take(top) {
+        println!(
+            "{:>7.2}  {:<13} {:>15}  {:>9}  {:>7}  {:>7}  {:?}{}",
+            guess.score,
+            format!("{:?}", guess.variant),
+            format!("{}/{}", guess.consumed, guess.total),
+            format!(
+                "{}/{}",
+                guess.enum_hits,
+                guess.enum_hits + guess.enum_misses
+            ),
+            guess.implausible_lengths,
+            guess.empty_reads,
+            guess.kind,
+            guess
+                .error
+                .as_ref()
+                .map(|err| format!(" ({err})"))
+                .unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context as _, Result};
+use common::{glob, hash};
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path};
+
+/// Logical paths by their name hash, from dictionary files: text with a
+/// path per line, or JSON with paths anywhere in it, e.g. `excel_paths.json`.
+#[derive(Default)]
+pub struct KnownPaths {
+    paths: HashMap<i32, String>,
+}
+
+impl KnownPaths {
+    pub fn load(files: &[impl AsRef<Path>]) -> Result<Self> {
+        let mut known = Self::default();
+        known.insert("BakedConfig/ConfigManifest.json");
+
+        for file in files {
+            let file = file.as_ref();
+            let text = fs::read_to_string(file).context(format!("Failed to read {file:?}"))?;
+            match serde_json::from_str::<Value>(&text) {
+                Ok(value) => known.insert_json(&value),
+                Err(_) => text.lines().for_each(|line| known.insert(line.trim())),
+            }
+        }
+
+        Ok(known)
+    }
+
+    fn insert_json(&mut self, value: &Value) {
+        match value {
+            Value::String(path) => self.insert(path),
+            Value::Array(values) => values.iter().for_each(|value| self.insert_json(value)),
+            Value::Object(map) => map.values().for_each(|value| self.insert_json(value)),
+            _ => {}
+        }
+    }
+
+    fn insert(&mut self, path: &str) {
+        // only things that look like asset paths
+        if path.contains('/') && !path.contains(char::is_whitespace) {
+            self.paths
+                .insert(hash::get_32bit_hash_const(path), path.to_string());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn get(&self, name_hash: i32) -> Option<&str> {
+        self.paths.get(&name_hash).map(String::as_str)
+    }
+
+    /// Known paths matching a glob, by name hash.
+    pub fn matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = (i32, &'a str)> + 'a {
+        self.paths
+            .iter()
+            .filter(move |(_, path)| glob::matches(pattern, path))
+            .map(|(name_hash, path)| (*name_hash, path.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KnownPaths;
+    use common::hash::get_32bit_hash_const;
+    use std::fs;
+
+    #[test]
+    fn test_known_paths() {
+        let dir = std::env::temp_dir().join(format!("tg-known-paths-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("excel_paths.json"),
+            r#"{"AvatarConfig": ["BakedConfig/ExcelOutput/AvatarConfig.bytes"]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("paths.txt"),
+            "BakedConfig/Config/Level.bytes\nnot a path\n",
+        )
+        .unwrap();
+
+        let known =
+            KnownPaths::load(&[dir.join("excel_paths.json"), dir.join("paths.txt")]).unwrap();
+        assert_eq!(known.len(), 3);
+        assert_eq!(
+            known.get(get_32bit_hash_const("BakedConfig/Config/Level.bytes")),
+            Some("BakedConfig/Config/Level.bytes")
+        );
+        assert_eq!(known.matching("BakedConfig/ExcelOutput/*").count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
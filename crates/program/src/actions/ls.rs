@@ -0,0 +1,238 @@
+use super::known_paths::KnownPaths;
+use crate::cli::ListFormat;
+use anyhow::Result;
+use common::glob;
+use serde::Serialize;
+use std::fmt::Write as _;
+use tg_asset_meta::design_index::DesignIndex;
+
+#[derive(Default)]
+pub struct LsFilter {
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Glob over known paths, entries of unknown path never match.
+    pub path: Option<String>,
+    /// Prefix of the block name.
+    pub block: Option<String>,
+}
+
+impl LsFilter {
+    #[inline]
+    fn filters_entries(&self) -> bool {
+        self.min_size.is_some() || self.max_size.is_some() || self.path.is_some()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListedFile {
+    pub block: String,
+    pub name_hash: i32,
+    pub size: i64,
+    pub entries: Vec<ListedEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListedEntry {
+    pub name_hash: i32,
+    pub offset: u32,
+    pub size: u32,
+    pub path: Option<String>,
+}
+
+/// Files and entries of an index passing `filter`. With an entry filter,
+/// files left without entries are dropped, without one every file of the
+/// block filter is listed, even those with no entries.
+pub fn select(
+    design_index: &DesignIndex,
+    known: &KnownPaths,
+    filter: &LsFilter,
+) -> Vec<ListedFile> {
+    design_index
+        .file_list
+        .iter()
+        .filter(|file_entry| {
+            filter
+                .block
+                .as_ref()
+                .is_none_or(|block| file_entry.file_byte_name.starts_with(block.as_str()))
+        })
+        .filter_map(|file_entry| {
+            let entries = file_entry
+                .data_entries
+                .iter()
+                .map(|data_entry| ListedEntry {
+                    name_hash: data_entry.name_hash,
+                    offset: data_entry.offset,
+                    size: data_entry.size,
+                    path: known.get(data_entry.name_hash).map(str::to_string),
+                })
+                .filter(|entry| {
+                    filter.min_size.is_none_or(|min| entry.size as u64 >= min)
+                        && filter.max_size.is_none_or(|max| entry.size as u64 <= max)
+                        && filter.path.as_ref().is_none_or(|pattern| {
+                            entry
+                                .path
+                                .as_ref()
+                                .is_some_and(|path| glob::matches(pattern, path))
+                        })
+                })
+                .collect::<Vec<_>>();
+
+            (!entries.is_empty() || !filter.filters_entries()).then(|| ListedFile {
+                block: file_entry.file_byte_name.clone(),
+                name_hash: file_entry.name_hash,
+                size: file_entry.size,
+                entries,
+            })
+        })
+        .collect()
+}
+
+pub fn print(files: &[ListedFile], format: ListFormat) -> Result<()> {
+    match format {
+        ListFormat::Table => {
+            for file in files {
+                println!(
+                    "{}.bytes  {} bytes  {} entries  (name hash {})",
+                    file.block,
+                    file.size,
+                    file.entries.len(),
+                    file.name_hash
+                );
+                for entry in &file.entries {
+                    println!(
+                        "  {:>11}  {:>10}  {:>10}  {}",
+                        entry.name_hash,
+                        entry.offset,
+                        entry.size,
+                        entry.path.as_deref().unwrap_or("<unknown>")
+                    );
+                }
+            }
+        }
+        ListFormat::Json => println!("{}", serde_json::to_string_pretty(files)?),
+        ListFormat::Csv => print!("{}", to_csv(files)),
+    }
+
+    let entries = files.iter().map(|file| file.entries.len()).sum::<usize>();
+    let unknown = files
+        .iter()
+        .flat_map(|file| &file.entries)
+        .filter(|entry| entry.path.is_none())
+        .count();
+    tracing::info!(
+        "{} blocks, {entries} entries, {unknown} of unknown path",
+        files.len()
+    );
+    Ok(())
+}
+
+/// One row per entry, with its block repeated.
+fn to_csv(files: &[ListedFile]) -> String {
+    let mut csv = String::from("Block,BlockSize,NameHash,Offset,Size,Path\n");
+    for file in files {
+        for entry in &file.entries {
+            let path = entry.path.as_deref().unwrap_or_default();
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                file.block,
+                file.size,
+                entry.name_hash,
+                entry.offset,
+                entry.size,
+                if path.contains([',', '"']) {
+                    format!("\"{}\"", path.replace('"', "\"\""))
+                } else {
+                    path.to_string()
+                }
+            );
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LsFilter, select, to_csv};
+    use crate::actions::known_paths::KnownPaths;
+    use common::hash::get_32bit_hash_const;
+    use std::io::Cursor;
+    use tg_asset_meta::{
+        design_index::{DesignIndex, FileEntry},
+        repack::{RepackOptions, repack},
+    };
+    use tg_bytes_util::FromBytes;
+
+    #[test]
+    fn test_select() {
+        let avatar = get_32bit_hash_const("BakedConfig/ExcelOutput/AvatarConfig.bytes");
+        let assets = [(avatar, vec![0; 10]), (1, vec![0; 2]), (2, vec![0; 30])]
+            .into_iter()
+            .collect();
+        let repacked = repack(
+            &assets,
+            &RepackOptions {
+                max_block_size: 12,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut index = Vec::new();
+        repacked.design_index.write(&mut index).unwrap();
+        let mut design_index = DesignIndex::from_bytes(&mut Cursor::new(index)).unwrap();
+        design_index.file_list.push(FileEntry {
+            name_hash: 3,
+            file_byte_name: "0".repeat(32),
+            size: 0,
+            data_count: 0,
+            data_entries: Vec::new(),
+            unk: 0,
+        });
+
+        let dir = std::env::temp_dir().join(format!("tg-ls-{}.txt", std::process::id()));
+        std::fs::write(&dir, "BakedConfig/ExcelOutput/AvatarConfig.bytes").unwrap();
+        let known = KnownPaths::load(&[&dir]).unwrap();
+        std::fs::remove_file(&dir).unwrap();
+
+        let entries = |filter: &LsFilter| {
+            select(&design_index, &known, filter)
+                .iter()
+                .map(|file| file.entries.len())
+                .sum::<usize>()
+        };
+        assert_eq!(entries(&LsFilter::default()), 3);
+        // a file without entries is listed unless entries are filtered
+        let files = select(&design_index, &known, &LsFilter::default()).len();
+        assert_eq!(files, repacked.design_index.file_list.len() + 1);
+        assert_eq!(
+            entries(&LsFilter {
+                max_size: Some(10),
+                ..Default::default()
+            }),
+            2
+        );
+        let block = &repacked.design_index.file_list[0].file_byte_name;
+        assert_eq!(
+            entries(&LsFilter {
+                block: Some(block[..8].to_string()),
+                ..Default::default()
+            }),
+            repacked.design_index.file_list[0].data_entries.len()
+        );
+
+        let by_path = select(
+            &design_index,
+            &known,
+            &LsFilter {
+                path: Some(String::from("BakedConfig/**")),
+                ..Default::default()
+            },
+        );
+        assert_eq!(by_path.len(), 1);
+        assert_eq!(by_path[0].entries.len(), 1);
+        assert!(to_csv(&by_path).ends_with(",BakedConfig/ExcelOutput/AvatarConfig.bytes\n"));
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{Context as _, Result};
+use common::source::AssetSource;
+use std::{fs, io::Cursor};
+use tg_asset_meta::mini_asset::MiniAsset;
+use tg_bytes_util::FromBytes;
+
+/// Prints every field of `M_DesignV.bytes`, from a source or a lone file.
+/// For sources, the index size it gives is checked against the index.
+pub fn print_meta(input: &str, source: Option<&dyn AssetSource>, json: bool) -> Result<()> {
+    let bytes = match source {
+        Some(source) => source.read("M_DesignV.bytes")?,
+        None => fs::read(input).context(format!("Failed to read {input}"))?,
+    };
+    let mini_asset = MiniAsset::from_bytes(&mut Cursor::new(&bytes))
+        .context(format!("Failed to parse the mini asset of {input}"))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&mini_asset)?);
+    } else {
+        println!("Version          {}", mini_asset.version_string());
+        println!("Size             {}", mini_asset.size);
+        println!(
+            "Unknown          {} {} {} {}",
+            mini_asset.unk_0c, mini_asset.unk_0e, mini_asset.unk_10, mini_asset.unk_14
+        );
+        println!("Revision         {}", mini_asset.revision_id);
+        println!(
+            "DesignIndex      DesignV_{}.bytes",
+            mini_asset.design_index_hash
+        );
+        println!("DesignIndexSize  {}", mini_asset.design_index_size);
+        println!("Timestamp        {}", mini_asset.timestamp);
+        println!("Trailing         {:02x?}", mini_asset.trailing);
+    }
+
+    if let Some(source) = source {
+        let name = format!("DesignV_{}.bytes", mini_asset.design_index_hash);
+        let index = source.read_block(&name, None)?;
+        if index.len() as u64 != mini_asset.design_index_size {
+            tracing::warn!(
+                "{name} is {} bytes but the mini asset says {}",
+                index.len(),
+                mini_asset.design_index_size
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,110 @@
+pub mod assets;
+pub mod compare_platforms;
 pub mod config;
+pub mod discover_excels;
 pub mod excel;
+pub mod explain;
+pub mod export_schema;
+pub mod extract;
+pub mod gen_schema;
+pub mod guess_type;
+pub mod known_paths;
+pub mod ls;
+pub mod meta;
+pub mod repack;
+pub mod report;
 pub mod textmap;
+pub mod update;
+pub mod verify;
+
+use crate::{PARSE_OPTIONS, cli::SourceArgs};
+use anyhow::{Context as _, Result};
+use common::{downloader, hash};
+use std::path::Path;
+use tg_parser::{ParseOptions, ParseReport};
+
+#[inline]
+pub fn parse_options() -> &'static ParseOptions {
+    PARSE_OPTIONS.get_or_init(ParseOptions::default)
+}
+
+pub fn log_report(path: &str, report: &ParseReport) {
+    for (enum_name, values) in &report.unknown_enums {
+        tracing::warn!("{path}: unknown {enum_name} values: {values:?}");
+    }
+
+    for probe in &report.typeindex_probes {
+        match probe.best() {
+            Some(best) => tracing::info!(
+                "{path}: unknown typeindex {} of {} at {} best matches {} ({} bytes)",
+                probe.typeindex,
+                probe.base,
+                probe.position,
+                best.type_name,
+                best.consumed
+            ),
+            None => tracing::info!(
+                "{path}: unknown typeindex {} of {} at {} matches no candidate staying in sync ({} parsed)",
+                probe.typeindex,
+                probe.base,
+                probe.position,
+                probe.candidates.len()
+            ),
+        }
+    }
+}
+
+/// Loads a single asset given as a local file, a raw 32-bit name hash or a
+/// logical path, downloading it from `input_url` for the last two.
+pub fn load_asset(asset: &str, input_url: Option<&str>, sources: &SourceArgs) -> Result<Vec<u8>> {
+    if Path::new(asset).is_file() {
+        return std::fs::read(asset).context(format!("Failed to read {asset}"));
+    }
+
+    let name_hash = asset
+        .parse::<i32>()
+        .unwrap_or_else(|_| hash::get_32bit_hash_const(asset));
+    let input_url = input_url.context("--input-url is required unless the asset is a file")?;
+
+    let source = sources.open(input_url)?;
+    downloader::download_all_design_data(source.as_ref(), None, vec![name_hash])?
+        .remove(&name_hash)
+        .context(format!(
+            "Asset {asset} ({name_hash}) not found in design index"
+        ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use tg_asset_meta::design_index::{DataEntry, DesignIndex, FileEntry};
+
+    /// (name_hash, offset, size)
+    pub type Entry = (i32, u32, u32);
+
+    /// An index of blocks named `block` holding `entries`.
+    pub fn design_index(files: &[(&str, &[Entry])]) -> DesignIndex {
+        DesignIndex {
+            unk_i64: 0,
+            file_count: files.len() as i32,
+            design_data_count: 0,
+            file_list: files
+                .iter()
+                .map(|(block, entries)| FileEntry {
+                    name_hash: 0,
+                    file_byte_name: block.to_string(),
+                    size: 0,
+                    data_count: entries.len() as i32,
+                    data_entries: entries
+                        .iter()
+                        .map(|(name_hash, offset, size)| DataEntry {
+                            name_hash: *name_hash,
+                            size: *size,
+                            offset: *offset,
+                        })
+                        .collect(),
+                    unk: 0,
+                })
+                .collect(),
+        }
+    }
+}
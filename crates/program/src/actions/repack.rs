@@ -0,0 +1,129 @@
+use anyhow::{Context as _, Result};
+use common::{downloader, hash, source::AssetSource};
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tg_asset_meta::repack::{self, RepackOptions};
+
+/// Builds a design data set of every asset of `source`, with the files of
+/// `replace` swapped in, into the `DesignData` folder of `out_folder`.
+pub fn repack(
+    source: &dyn AssetSource,
+    replace: Option<&Path>,
+    out_folder: &Path,
+    revision_id: Option<u32>,
+) -> Result<()> {
+    let revision_id = match revision_id {
+        Some(revision_id) => revision_id,
+        None => downloader::fetch_mini_asset(source)?.revision_id,
+    };
+    // a data set missing assets would pass for a whole revision
+    let data = downloader::download_layered_design_data(&[source], None, Vec::with_capacity(0))?;
+    data.ensure_complete()
+        .context("Not repacking design data with blocks missing")?;
+    let mut assets = data.assets;
+
+    if let Some(replace) = replace {
+        let mut replaced = 0;
+        for (name_hash, path) in replacement_files(replace)? {
+            let data = fs::read(&path).context(format!("Failed to read {path:?}"))?;
+            if assets.insert(name_hash, data).is_none() {
+                tracing::info!("Adding {path:?} ({name_hash})");
+            }
+            replaced += 1;
+        }
+        tracing::info!("Replaced or added {replaced} assets");
+    }
+
+    let repacked = repack::repack(
+        &assets,
+        &RepackOptions {
+            revision_id,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            own_block: vec![hash::get_32bit_hash_const(
+                "BakedConfig/ConfigManifest.json",
+            )],
+            ..Default::default()
+        },
+    )?;
+    repacked.write_to(&out_folder.join("DesignData"))?;
+
+    tracing::info!(
+        "Packed {} assets into {} blocks, index DesignV_{}.bytes",
+        assets.len(),
+        repacked.blocks.len(),
+        repacked.mini_asset.design_index_hash
+    );
+    Ok(())
+}
+
+/// Files under `dir` by the name hash they replace: a file named by a raw
+/// hash, e.g. `-1703948225.bytes`, or else by its path relative to `dir`,
+/// e.g. `BakedConfig/ExcelOutput/AvatarConfig.bytes`.
+fn replacement_files(dir: &Path) -> Result<Vec<(i32, std::path::PathBuf)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        for entry in fs::read_dir(&current).context(format!("Failed to read {current:?}"))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+
+            let raw_hash = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<i32>().ok());
+            let name_hash = match raw_hash {
+                Some(name_hash) => name_hash,
+                None => {
+                    let logical_path = path.strip_prefix(dir)?.to_string_lossy().replace('\\', "/");
+                    hash::get_32bit_hash_const(&logical_path)
+                }
+            };
+            files.push((name_hash, path));
+        }
+    }
+
+    let mut seen = HashMap::new();
+    for (name_hash, path) in &files {
+        if let Some(other) = seen.insert(name_hash, path) {
+            anyhow::bail!("{other:?} and {path:?} both replace {name_hash}");
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use common::source::MemorySource;
+    use std::collections::HashMap;
+    use tg_asset_meta::repack::{RepackOptions, repack};
+
+    #[test]
+    fn test_repack_missing_block() {
+        let assets = HashMap::from([(1, b"aaaa".to_vec()), (2, b"bbbb".to_vec())]);
+        let repacked = repack(
+            &assets,
+            &RepackOptions {
+                max_block_size: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut source = MemorySource {
+            files: repacked.files().unwrap().into_iter().collect(),
+        };
+        source
+            .files
+            .remove(&format!("{}.bytes", repacked.blocks[0].0));
+
+        let out = std::env::temp_dir().join(format!("tg-repack-{}", std::process::id()));
+        assert!(super::repack(&source, None, &out, Some(1)).is_err());
+        assert!(!out.exists());
+    }
+}
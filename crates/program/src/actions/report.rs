@@ -0,0 +1,113 @@
+use anyhow::{Context as _, Result};
+use common::{downloader::LayeredDesignData, source::AssetSource};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    sync::{LazyLock, Mutex},
+};
+use tg_asset_meta::baked_header::BakedHeader;
+use tg_parser::{ParseReport, TypeindexProbe};
+
+static REPORTS: LazyLock<Mutex<BTreeMap<String, FileReport>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Per-file summary written to `ParseReport.json`, so header and schema
+/// changes show up in a diff between runs.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FileReport {
+    pub type_name: String,
+    pub header: Option<BakedHeader>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub unknown_enums: BTreeMap<String, BTreeSet<i128>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub typeindex_probes: Vec<TypeindexProbe>,
+}
+
+impl FileReport {
+    pub fn new(type_name: &str, header: Option<BakedHeader>) -> Self {
+        Self {
+            type_name: type_name.to_string(),
+            header,
+            rows: None,
+            error: None,
+            unknown_enums: BTreeMap::new(),
+            typeindex_probes: Vec::new(),
+        }
+    }
+
+    pub fn with_parse_report(mut self, report: &ParseReport) -> Self {
+        self.unknown_enums = report.unknown_enums.clone();
+        self.typeindex_probes = report.typeindex_probes.clone();
+        self
+    }
+}
+
+pub fn record(path: &str, report: FileReport) {
+    REPORTS.lock().unwrap().insert(path.to_string(), report);
+}
+
+/// Writes `ParseReport.json`. With `merge`, files that weren't parsed this
+/// run keep their entry from the previous one.
+pub fn write(out_folder: &Path, merge: bool) -> Result<()> {
+    let out_path = out_folder.join("ParseReport.json");
+    let mut merged: BTreeMap<String, serde_json::Value> = match fs::read(&out_path) {
+        Ok(previous) if merge => serde_json::from_slice(&previous).unwrap_or_default(),
+        _ => BTreeMap::new(),
+    };
+
+    let reports = REPORTS.lock().unwrap();
+    for (path, report) in &*reports {
+        merged.insert(path.clone(), serde_json::to_value(report)?);
+    }
+
+    fs::write(out_path, serde_json::to_string_pretty(&merged)?)
+        .context("Failed to write ParseReport.json")
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct LayerReport {
+    source: String,
+    assets: usize,
+}
+
+/// Writes `Layers.json`, naming the layer every asset was taken from. Only
+/// written when overlays are in use.
+pub fn write_layers(
+    out_folder: &Path,
+    layers: &[Box<dyn AssetSource>],
+    data: &LayeredDesignData,
+) -> Result<()> {
+    if layers.len() < 2 {
+        return Ok(());
+    }
+
+    let layer_reports = layers
+        .iter()
+        .enumerate()
+        .map(|(layer, source)| LayerReport {
+            source: source.describe(),
+            assets: data.origins.values().filter(|l| **l == layer).count(),
+        })
+        .collect::<Vec<_>>();
+    for report in &layer_reports {
+        tracing::info!("{} assets from {}", report.assets, report.source);
+    }
+
+    fs::create_dir_all(out_folder)?;
+    fs::write(
+        out_folder.join("Layers.json"),
+        serde_json::to_string_pretty(&serde_json::json!({
+            "Layers": layer_reports,
+            "Origins": data.origins.iter().collect::<BTreeMap<_, _>>(),
+        }))?,
+    )
+    .context("Failed to write Layers.json")
+}
@@ -4,10 +4,13 @@ use anyhow::Result;
 use common::hash::get_32bit_hash_const;
 use serde::Serialize;
 use serde_json::{Map, Value};
+use tg_asset_meta::baked_header::{BakedHeader, ExcelHeader};
 use tg_bytes_util::{ExistFlag, FromBytes};
 
 use crate::COUNTER_TEXTMAPS;
 
+use super::report::{self, FileReport};
+
 pub const TEXTMAP_PATHS: [(&str, i32); 28] = [
     (
         "TextMapEN.json",
@@ -189,17 +192,28 @@ pub fn parse_all_textmap(
             continue;
         };
 
-        // Skip empty first byte
-        let asset = if !asset.is_empty() && asset[0] == 0 {
-            &asset[1..]
-        } else {
-            asset
+        let report_path = format!("TextMap/{name}");
+        let (header, asset) = match ExcelHeader::split(asset) {
+            Ok(split) => split,
+            Err(err) => {
+                tracing::error!("{report_path}: {err}, not parsing it");
+                let mut file_report = FileReport::new("TextMapRow", None);
+                file_report.error = Some(err.to_string());
+                report::record(&report_path, file_report);
+                continue;
+            }
         };
 
         let out_path = out_folder.join(name);
         let mut cursor = Cursor::new(asset);
 
-        let Ok(parsed) = Vec::<TextMapRow>::from_bytes(&mut cursor) else {
+        let parsed = Vec::<TextMapRow>::from_bytes(&mut cursor);
+        let mut file_report = FileReport::new("TextMapRow", Some(BakedHeader::Excel(header)));
+        file_report.rows = parsed.as_ref().ok().map(Vec::len);
+        file_report.error = parsed.as_ref().err().map(|err| err.to_string());
+        report::record(&report_path, file_report);
+
+        let Ok(parsed) = parsed else {
             continue;
         };
 
@@ -0,0 +1,177 @@
+//! Incremental runs. `all` and `update` keep the design index they parsed in
+//! `.tg-state/DesignData` of the output directory; `update` compares it with
+//! the new index and only fetches and parses the entries that changed.
+
+use anyhow::{Context as _, Result};
+use common::{
+    downloader::{self, LayeredDesignData},
+    hash,
+    source::{AssetSource, DirectorySource},
+};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use tg_asset_meta::design_index::DesignIndex;
+
+const STATE_DIR: &str = ".tg-state";
+const STAGED_STATE_DIR: &str = ".tg-state.new";
+
+/// Entries of `current` that are new or moved since `previous`, and the
+/// number of entries that are gone.
+pub fn changed_entries(previous: &DesignIndex, current: &DesignIndex) -> (HashSet<i32>, usize) {
+    let previous = downloader::entry_keys(previous);
+    let current = downloader::entry_keys(current);
+
+    let changed = current
+        .iter()
+        .filter(|(name_hash, key)| previous.get(name_hash) != Some(key))
+        .map(|(name_hash, _)| *name_hash)
+        .collect();
+    let removed = previous
+        .keys()
+        .filter(|name_hash| !current.contains_key(name_hash))
+        .count();
+
+    (changed, removed)
+}
+
+/// Fetches the new design index into the staged state, then only the blocks
+/// holding entries that changed since the state of the previous run. Without
+/// a previous state everything is fetched.
+pub fn fetch_changed(
+    source: &dyn AssetSource,
+    out_folder: &Path,
+    save_bytes_folder: Option<&Path>,
+) -> Result<LayeredDesignData> {
+    let current = stage(source, out_folder, save_bytes_folder)?;
+
+    let state = out_folder.join(STATE_DIR);
+    let previous = state
+        .join("DesignData")
+        .is_dir()
+        .then(|| downloader::fetch_design_index(&DirectorySource::saved_bytes(&state), None))
+        .transpose()
+        .context("Failed to read the previous design index, remove .tg-state to start over")?;
+
+    let filter_hashes = match &previous {
+        Some(previous) => {
+            let (changed, removed) = changed_entries(previous, &current);
+            tracing::info!(
+                "{} of {} entries changed, {removed} removed",
+                changed.len(),
+                current.design_data_count
+            );
+            if changed.is_empty() {
+                return Ok(LayeredDesignData::default());
+            }
+            // configs can't be found without the manifest, changed or not
+            changed
+                .into_iter()
+                .chain([hash::get_32bit_hash_const(
+                    "BakedConfig/ConfigManifest.json",
+                )])
+                .collect()
+        }
+        None => {
+            tracing::info!("No previous state in {out_folder:?}, fetching everything");
+            Vec::new()
+        }
+    };
+
+    Ok(downloader::download_design_files(
+        &[(source, &current, save_bytes_folder)],
+        &filter_hashes,
+    ))
+}
+
+/// Stages the design index of `source` and fetches everything it lists, so
+/// the state of a full run is the index that was parsed.
+pub fn fetch_all(
+    source: &dyn AssetSource,
+    out_folder: &Path,
+    save_bytes_folder: Option<&Path>,
+) -> Result<LayeredDesignData> {
+    let current = stage(source, out_folder, save_bytes_folder)?;
+    Ok(downloader::download_design_files(
+        &[(source, &current, save_bytes_folder)],
+        &[],
+    ))
+}
+
+/// Fetches the design index of `source` into the staged state, and into
+/// `save_bytes_folder` when given.
+fn stage(
+    source: &dyn AssetSource,
+    out_folder: &Path,
+    save_bytes_folder: Option<&Path>,
+) -> Result<DesignIndex> {
+    let staged = stage_dir(out_folder)?;
+    let design_index = downloader::fetch_design_index(source, Some(&staged))?;
+    if let Some(folder) = save_bytes_folder {
+        copy_dir(&staged.join("DesignData"), &folder.join("DesignData"))?;
+    }
+    Ok(design_index)
+}
+
+/// Makes the staged state the one the next update compares against, once
+/// the run is done. A run with blocks that failed isn't committed, so the
+/// next update fetches their entries again.
+pub fn commit(out_folder: &Path, data: &LayeredDesignData) -> Result<()> {
+    if !data.failed.is_empty() {
+        tracing::warn!(
+            "{} blocks failed, not saving the state of this run",
+            data.failed.len()
+        );
+        return Ok(());
+    }
+
+    let state = out_folder.join(STATE_DIR);
+    if state.is_dir() {
+        fs::remove_dir_all(&state)?;
+    }
+    fs::rename(out_folder.join(STAGED_STATE_DIR), &state).context("Failed to save .tg-state")
+}
+
+fn stage_dir(out_folder: &Path) -> Result<PathBuf> {
+    let staged = out_folder.join(STAGED_STATE_DIR);
+    if staged.is_dir() {
+        fs::remove_dir_all(&staged)?;
+    }
+    fs::create_dir_all(&staged)?;
+    Ok(staged)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        fs::copy(entry.path(), to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::changed_entries;
+    use crate::actions::tests::design_index as index;
+    #[test]
+    fn test_changed_entries() {
+        let previous = index(&[
+            ("a", &[(1, 0, 4), (2, 4, 4)]),
+            ("b", &[(3, 0, 4), (4, 4, 4)]),
+        ]);
+        let current = index(&[
+            ("a", &[(1, 0, 4), (2, 4, 4)]),
+            // same place in a new block may still hold new data
+            ("c", &[(3, 0, 4), (5, 4, 4)]),
+        ]);
+
+        let (mut changed, removed) = changed_entries(&previous, &current);
+        let mut changed = changed.drain().collect::<Vec<_>>();
+        changed.sort();
+        assert_eq!(changed, [3, 5]);
+        assert_eq!(removed, 1);
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context as _, Result};
+use common::{
+    downloader,
+    source::{self, HttpOptions, Platform},
+};
+use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+use std::{collections::HashMap, fs, io::Cursor, path::Path};
+use tg_asset_meta::{
+    design_index::{DesignIndex, FileEntry},
+    integrity::{self, BlockError},
+    mini_asset::MiniAsset,
+};
+use tg_bytes_util::FromBytes;
+
+/// Outcome of checking one block.
+enum Check {
+    Ok,
+    Missing(String),
+    Failed(BlockError),
+}
+
+/// Checks every block of a saved bytes folder, a Persistent folder, an
+/// archive or a `--cache-dir` against its design index, without parsing any
+/// of it. Fails if anything is off.
+pub fn verify(path: &Path, platform: Platform) -> Result<()> {
+    let checks = if path.join("blocks").is_dir() {
+        verify_cache(path)?
+    } else {
+        verify_source(path, platform)?
+    };
+
+    let mut missing = 0;
+    let mut errors = Vec::new();
+    let mut digest_errors = Vec::new();
+    for check in &checks {
+        match check {
+            Check::Ok => {}
+            Check::Missing(name) => {
+                tracing::error!("{name} is missing");
+                missing += 1;
+            }
+            Check::Failed(err @ BlockError::DigestMismatch { .. }) => digest_errors.push(err),
+            Check::Failed(err) => errors.push(err),
+        }
+    }
+
+    // the digest is checked last, so only of blocks without other errors
+    let checked = checks.len() - missing - errors.len();
+    if !digest_errors.is_empty() && !integrity::names_are_digests(checked, digest_errors.len()) {
+        tracing::warn!(
+            "No block name matched the MD5 of its content, names may be another hash, digests not checked"
+        );
+        digest_errors.clear();
+    }
+
+    for err in errors.iter().chain(&digest_errors) {
+        tracing::error!("{err}");
+    }
+
+    let problems = missing + errors.len() + digest_errors.len();
+    tracing::info!("Checked {} blocks, {problems} problems", checks.len());
+    if problems > 0 {
+        anyhow::bail!("{problems} problems found in {path:?}");
+    }
+
+    Ok(())
+}
+
+fn verify_source(path: &Path, platform: Platform) -> Result<Vec<Check>> {
+    let source = source::open_with(
+        path.to_str().context("Invalid path")?,
+        platform,
+        HttpOptions::default(),
+    )?;
+    let design_index = downloader::fetch_design_index(source.as_ref(), None)?;
+
+    Ok(design_index
+        .file_list
+        .par_iter()
+        .map(|file_entry| {
+            let name = format!("{}.bytes", file_entry.file_byte_name);
+            match source.read_block(&name, Some(file_entry.size as u64)) {
+                Ok(data) => check_block(file_entry, &data),
+                Err(_) => Check::Missing(name),
+            }
+        })
+        .collect())
+}
+
+/// Blocks of a cache are checked against the indices it holds. Blocks no
+/// index names, indices and asset files only get their digest checked, and
+/// pruned ones aren't missed.
+fn verify_cache(path: &Path) -> Result<Vec<Check>> {
+    let blocks = path.join("blocks");
+
+    let mut entries: HashMap<String, FileEntry> = HashMap::new();
+    for mini in fs::read_dir(path.join("mini")).into_iter().flatten() {
+        let Ok(bytes) = fs::read(mini?.path().join("M_DesignV.bytes")) else {
+            continue;
+        };
+        let mini_asset = MiniAsset::from_bytes(&mut Cursor::new(&bytes))?;
+        let Ok(bytes) =
+            fs::read(blocks.join(format!("DesignV_{}.bytes", mini_asset.design_index_hash)))
+        else {
+            continue;
+        };
+
+        let design_index = DesignIndex::from_bytes(&mut Cursor::new(&bytes))?;
+        for file_entry in design_index.file_list {
+            entries.insert(file_entry.file_byte_name.clone(), file_entry);
+        }
+    }
+
+    let names = fs::read_dir(&blocks)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(names
+        .par_iter()
+        .filter(|name| name.ends_with(".bytes"))
+        .filter_map(|name| {
+            let data = fs::read(blocks.join(name)).ok()?;
+            // indices, DesignV_<md5>.bytes and the like, are named by their
+            // MD5 too
+            let stem = name.trim_end_matches(".bytes");
+            let stem = stem.split_once("V_").map_or(stem, |(_, digest)| digest);
+            Some(match entries.get(stem) {
+                Some(file_entry) => check_block(file_entry, &data),
+                None => check_block(
+                    &FileEntry {
+                        name_hash: 0,
+                        file_byte_name: stem.to_string(),
+                        size: data.len() as i64,
+                        data_count: 0,
+                        data_entries: Vec::new(),
+                        unk: 0,
+                    },
+                    &data,
+                ),
+            })
+        })
+        .collect())
+}
+
+fn check_block(file_entry: &FileEntry, data: &[u8]) -> Check {
+    match file_entry
+        .check_size(data)
+        .and_then(|_| file_entry.check_entries(data.len() as u64))
+        .and_then(|_| file_entry.check_digest(data))
+    {
+        Ok(()) => Check::Ok,
+        Err(err) => Check::Failed(err),
+    }
+}
@@ -1,6 +1,20 @@
-use std::path::PathBuf;
+use anyhow::{Context as _, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use common::{
+    cache::{BlockCache, CachedSource},
+    downloader,
+    source::{self, AssetSource, HttpOptions, Platform},
+};
+use tg_asset_meta::asset_index::IndexKind;
+use tg_parser::{DictionaryMode, Layouts, ParseOptions, TypeEmit, TypeNamePolicy};
+
+use crate::registry::{SchemaBundle, SchemaRegistry, SelectedBundle};
 
 #[derive(Parser)]
 #[command(
@@ -17,7 +31,7 @@ pub struct Cli {
 pub enum Command {
     /// Process textmap only
     Textmap {
-        /// Persistent path or design data URL
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive
         input_url: String,
         /// Output directory
         output_dir: PathBuf,
@@ -29,6 +43,13 @@ pub enum Command {
         /// Save .bytes file after downloading the files
         #[arg(long, name = "save-bytes-file")]
         save_bytes_file: bool,
+
+        /// Design data layered over input_url, e.g. Persistent over StreamingAssets (repeatable, last wins)
+        #[arg(long)]
+        overlay: Vec<String>,
+
+        #[command(flatten)]
+        sources: SourceArgs,
     },
 
     /// Process excel only
@@ -36,18 +57,253 @@ pub enum Command {
 
     /// Process excel, config, textmap parse
     All(ExcelArgs),
+
+    /// Like all, but only fetch and parse what changed since the last all or update into the output directory
+    Update(ExcelArgs),
+
+    /// Rank data.json types by how well they parse a single asset
+    GuessType {
+        /// data.json schema file path
+        data_json: String,
+        /// Asset to guess: a local file, a logical path (e.g. BakedConfig/...) or a 32-bit name hash
+        asset: String,
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive, required unless the asset is a local file
+        #[arg(long, name = "input-url")]
+        input_url: Option<String>,
+        /// Number of guesses to print
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Declarative custom parsers file, defaults to custom_parsers.json next to data.json
+        #[arg(long, name = "custom-parsers")]
+        custom_parsers: Option<PathBuf>,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Generate data.json and excel_paths.json from an Il2CppDumper dump.cs
+    GenSchema {
+        /// dump.cs file path
+        dump_cs: PathBuf,
+        /// Output directory
+        output_dir: PathBuf,
+        /// Hint file with Typeindex maps and overrides the dump can't provide
+        #[arg(long)]
+        hints: Option<PathBuf>,
+    },
+
+    /// Find the ExcelOutput file of every row type by path hash and write excel_paths.json
+    DiscoverExcels {
+        /// data.json schema file path
+        data_json: String,
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive
+        input_url: String,
+        /// Output directory
+        output_dir: PathBuf,
+        /// Declarative custom parsers file, defaults to custom_parsers.json next to data.json
+        #[arg(long, name = "custom-parsers")]
+        custom_parsers: Option<PathBuf>,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Check the blocks of a saved bytes folder, Persistent folder, archive or cache against their index
+    Verify {
+        /// Saved bytes or Persistent folder, archive, or --cache-dir
+        path: PathBuf,
+        /// Client platform whose design data is checked
+        #[arg(long, value_enum, default_value_t = PlatformArg::Windows)]
+        platform: PlatformArg,
+    },
+
+    /// List the blocks and entries of a design index
+    Ls {
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive
+        input_url: String,
+        /// Known paths to resolve hashes with: text with a path per line, or JSON such as excel_paths.json (repeatable)
+        #[arg(long)]
+        paths: Vec<PathBuf>,
+        /// Only entries at least this big, e.g. 1K
+        #[arg(long, name = "min-size", value_parser = parse_size)]
+        min_size: Option<u64>,
+        /// Only entries at most this big, e.g. 1M
+        #[arg(long, name = "max-size", value_parser = parse_size)]
+        max_size: Option<u64>,
+        /// Only entries whose known path matches this glob, e.g. "BakedConfig/ExcelOutput/*"
+        #[arg(long)]
+        path: Option<String>,
+        /// Only blocks whose name starts with this
+        #[arg(long)]
+        block: Option<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Write the raw bytes of single assets, by logical path, raw 32-bit hash, or glob over --paths
+    Extract {
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive
+        input_url: String,
+        /// Output directory, assets are written under their path, or as <hash>.bytes when unknown
+        output_dir: PathBuf,
+        /// Assets to extract: BakedConfig/..., a 32-bit name hash, or a glob such as "BakedConfig/ExcelOutput/*"
+        #[arg(required = true)]
+        assets: Vec<String>,
+        /// Known paths for globs and for naming hashes: text with a path per line, or JSON such as excel_paths.json (repeatable)
+        #[arg(long)]
+        paths: Vec<PathBuf>,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// List or download the files of the Lua, video or audio index
+    Assets {
+        /// URL, directory or archive holding the index's folder, e.g. Video/M_VideoV.bytes
+        input_url: String,
+        /// Index to read
+        #[arg(value_enum)]
+        kind: IndexKindArg,
+        /// Only files whose name matches this glob, e.g. "*.usm"
+        #[arg(long)]
+        filter: Option<String>,
+        /// Download the selected files into this directory instead of listing them
+        #[arg(long)]
+        download: Option<PathBuf>,
+        /// List as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Rebuild a design data set from a source with some assets replaced, e.g. for a local test environment
+    Repack {
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive
+        input_url: String,
+        /// Output directory, the data set is written into its DesignData folder
+        output_dir: PathBuf,
+        /// Directory of replacement assets, by logical path (BakedConfig/...) or named by a raw 32-bit hash
+        #[arg(long)]
+        replace: Option<PathBuf>,
+        /// Revision id of the new data set, defaults to the source's
+        #[arg(long)]
+        revision: Option<u32>,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Print the M_DesignV.bytes header of a source
+    Meta {
+        /// Design data URL, Persistent or saved bytes directory, .zip/.tar(.gz) archive, or an M_DesignV.bytes file
+        input_url: String,
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Compare the design index of --platform with another platform's for the same revision
+    ComparePlatforms {
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive
+        input_url: String,
+        /// Platform to compare --platform with
+        other: PlatformArg,
+        /// Also write the comparison as JSON
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Other platform's design data, when it's not in input_url
+        #[arg(long, name = "other-url")]
+        other_url: Option<String>,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Print an annotated hexdump of parsing a single asset as a type
+    Explain {
+        /// data.json schema file path
+        data_json: String,
+        /// Asset to explain: a local file, a logical path (e.g. BakedConfig/...) or a 32-bit name hash
+        asset: String,
+        /// Type the asset starts with, e.g. RPG.GameCore.AvatarConfig
+        root_type: String,
+        /// Design data URL, Persistent or saved bytes directory, or .zip/.tar(.gz) archive, required unless the asset is a local file
+        #[arg(long, name = "input-url")]
+        input_url: Option<String>,
+        /// Parse as an array of root_type rows, like Excel files
+        #[arg(long)]
+        array: bool,
+        /// Also write the trace as JSON
+        #[arg(long, name = "trace-json")]
+        trace_json: Option<PathBuf>,
+        /// Declarative custom parsers file, defaults to custom_parsers.json next to data.json
+        #[arg(long, name = "custom-parsers")]
+        custom_parsers: Option<PathBuf>,
+
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+
+    /// Export the schema of a type as an ImHex pattern or Kaitai Struct file
+    ExportSchema {
+        /// data.json schema file path
+        data_json: String,
+        /// Root type, e.g. RPG.GameCore.AvatarConfig
+        root_type: String,
+        /// Output file, .hexpat or .ksy
+        output: PathBuf,
+        /// Output format, guessed from the output extension when omitted
+        #[arg(long, value_enum)]
+        format: Option<SchemaFormat>,
+        /// Declarative custom parsers file, defaults to custom_parsers.json next to data.json
+        #[arg(long, name = "custom-parsers")]
+        custom_parsers: Option<PathBuf>,
+    },
 }
 
 #[derive(Args)]
 pub struct ExcelArgs {
-    /// data.json schema file path
-    pub data_json: String,
-    /// excel_paths.json file path
-    pub excel_path_json: String,
-    /// Persistent path or design data URL
-    pub input_url: String,
-    /// Output directory
-    pub output_dir: PathBuf,
+    /// [DATA_JSON EXCEL_PATHS_JSON] INPUT_URL OUTPUT_DIR: the design data URL, Persistent or
+    /// saved bytes directory, or .zip/.tar(.gz) archive, and the output directory, optionally
+    /// after data.json and excel_paths.json as an alternative to the flags below
+    #[arg(
+        num_args = 2..=4,
+        required = true,
+        value_names = ["DATA_JSON", "EXCEL_PATHS_JSON", "INPUT_URL", "OUTPUT_DIR"]
+    )]
+    positionals: Vec<String>,
+
+    /// Design data layered over input_url, e.g. Persistent over StreamingAssets (repeatable, last wins)
+    #[arg(long)]
+    pub overlay: Vec<String>,
+
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    /// data.json schema file path, overrides the schema bundle's
+    #[arg(long, name = "data-json")]
+    pub data_json: Option<PathBuf>,
+
+    /// excel_paths.json file path, overrides the schema bundle's
+    #[arg(long, name = "excel-paths")]
+    pub excel_paths: Option<PathBuf>,
+
+    /// Directory of schema bundles, the one matching the game revision is used
+    #[arg(long, name = "schema-registry")]
+    pub schema_registry: Option<PathBuf>,
+
+    /// Schema bundle to use instead of the one matching the game revision
+    #[arg(long, name = "schema-bundle")]
+    pub schema_bundle: Option<String>,
 
     /// Parse full textmap structure as array, rather than just key-value pair
     #[arg(long)]
@@ -61,7 +317,382 @@ pub struct ExcelArgs {
     #[arg(long, name = "log-error")]
     pub log_error: bool,
 
-    /// Additional configs path to parse, with type as key, and array of paths as values
+    /// Additional configs path to parse, with type as key, and array of paths as values,
+    /// overrides the schema bundle's
     #[arg(long, name = "config-paths")]
     pub config_paths: Option<PathBuf>,
+
+    /// Try every derived class when a Typeindex discriminant is missing from the schema
+    #[arg(long, name = "probe-typeindex")]
+    pub probe_typeindex: bool,
+
+    /// Emit enums as {Name, Value} objects rather than just the member name
+    #[arg(long, name = "enum-with-value")]
+    pub enum_with_value: bool,
+
+    /// How dictionaries are written out
+    #[arg(long, name = "dictionary-mode", value_enum, default_value_t = DictionaryModeArg::Object)]
+    pub dictionary_mode: DictionaryModeArg,
+
+    /// Dictionary mode for the fields of a single type, as TYPE=MODE (repeatable)
+    #[arg(long, name = "dictionary-mode-for", value_parser = parse_type_dictionary_mode)]
+    pub dictionary_mode_for: Vec<(String, DictionaryModeArg)>,
+
+    /// Which classes get a type discriminator field
+    #[arg(long, name = "type-emit", value_enum, default_value_t = TypeEmitArg::Polymorphic)]
+    pub type_emit: TypeEmitArg,
+
+    /// Name of the type discriminator field
+    #[arg(long, name = "type-key", default_value = "$type")]
+    pub type_key: String,
+
+    /// Prefix to strip from type names (repeatable)
+    #[arg(long, name = "strip-type-prefix")]
+    pub strip_type_prefix: Vec<String>,
+
+    /// Suffix to strip from type names (repeatable)
+    #[arg(long, name = "strip-type-suffix", default_values_t = [String::from("Inner")])]
+    pub strip_type_suffix: Vec<String>,
+
+    /// Write type names without their namespace
+    #[arg(long, name = "short-type-names")]
+    pub short_type_names: bool,
+
+    /// Declarative custom parsers file, defaults to the schema bundle's or
+    /// custom_parsers.json next to data.json
+    #[arg(long, name = "custom-parsers")]
+    pub custom_parsers: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct SourceArgs {
+    /// Client platform of the design data
+    #[arg(long, value_enum, default_value_t = PlatformArg::Windows)]
+    pub platform: PlatformArg,
+
+    /// Keep downloaded design data blocks here and reuse them on later runs
+    #[arg(long, name = "cache-dir")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Prune the least recently used blocks after the run until the cache fits, e.g. 2G or 500M
+    #[arg(long, name = "cache-max-size", value_parser = parse_size)]
+    pub cache_max_size: Option<u64>,
+
+    /// Fail instead of downloading anything that isn't in the cache
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Files downloaded at once
+    #[arg(long, default_value_t = HttpOptions::default().concurrency)]
+    pub concurrency: usize,
+
+    /// Failed attempts in a row before giving up on a file
+    #[arg(long, default_value_t = HttpOptions::default().max_retries)]
+    pub retries: usize,
+
+    /// Seconds to wait for a response or for more of its body
+    #[arg(long, default_value_t = HttpOptions::default().read_timeout.as_secs())]
+    pub timeout: u64,
+
+    /// Total download rate cap in bytes per second, e.g. 10M
+    #[arg(long, name = "max-bandwidth", value_parser = parse_size)]
+    pub max_bandwidth: Option<u64>,
+}
+
+impl SourceArgs {
+    /// Opens a source, through the cache when it's remote and a cache is set.
+    pub fn open(&self, input_url: &str) -> Result<Box<dyn AssetSource>> {
+        self.open_platform(input_url, self.platform.into())
+    }
+
+    /// [`Self::open`] for a platform other than `--platform`.
+    pub fn open_platform(
+        &self,
+        input_url: &str,
+        platform: Platform,
+    ) -> Result<Box<dyn AssetSource>> {
+        let source = source::open_with(
+            input_url,
+            platform,
+            HttpOptions {
+                concurrency: self.concurrency,
+                max_retries: self.retries,
+                read_timeout: Duration::from_secs(self.timeout),
+                max_bandwidth: self.max_bandwidth,
+                ..Default::default()
+            },
+        )?;
+        if !source.is_remote() {
+            return Ok(source);
+        }
+
+        match &self.cache_dir {
+            Some(dir) => Ok(Box::new(CachedSource::new(
+                source,
+                BlockCache::new(dir)?,
+                self.offline,
+            ))),
+            None if self.offline => anyhow::bail!("--offline needs --cache-dir for {input_url}"),
+            None => Ok(source),
+        }
+    }
+
+    /// Prunes the cache down to `--cache-max-size`, if both are set.
+    pub fn prune(&self) -> Result<()> {
+        if let (Some(dir), Some(max_size)) = (&self.cache_dir, self.cache_max_size) {
+            let freed = BlockCache::new(dir)?.prune(max_size)?;
+            tracing::info!("Pruned {freed} bytes from the cache");
+        }
+        Ok(())
+    }
+}
+
+/// Schema files of a run, from flags or the selected schema bundle.
+pub struct SchemaFiles {
+    pub data_json: PathBuf,
+    pub excel_paths: PathBuf,
+    pub config_paths: Option<PathBuf>,
+    pub custom_parsers: Option<PathBuf>,
+    pub bundle: Option<SelectedBundle>,
+}
+
+impl ExcelArgs {
+    /// The design data and output directory, the last two positionals.
+    pub fn input_output(&self) -> Result<(&str, &Path)> {
+        self.positional_schema()?;
+        let [.., input_url, output_dir] = self.positionals.as_slice() else {
+            unreachable!("clap requires two positionals");
+        };
+        Ok((input_url, Path::new(output_dir)))
+    }
+
+    /// data.json and excel_paths.json when given as the first two of four
+    /// positionals.
+    fn positional_schema(&self) -> Result<Option<(PathBuf, PathBuf)>> {
+        match self.positionals.as_slice() {
+            [_, _] => Ok(None),
+            [data_json, excel_paths, _, _] => {
+                if self.data_json.is_some() || self.excel_paths.is_some() {
+                    anyhow::bail!(
+                        "data.json and excel_paths.json are given both as arguments and flags"
+                    );
+                }
+                Ok(Some((data_json.into(), excel_paths.into())))
+            }
+            _ => anyhow::bail!(
+                "expected INPUT_URL OUTPUT_DIR, or DATA_JSON EXCEL_PATHS_JSON INPUT_URL OUTPUT_DIR"
+            ),
+        }
+    }
+
+    /// Picks the schema files, by the revision of `source` when a registry is
+    /// given. The game revision is only downloaded when the bundle isn't
+    /// named.
+    pub fn schema_files(&self, source: &dyn AssetSource) -> Result<SchemaFiles> {
+        let (data_json, excel_paths) = match self.positional_schema()? {
+            Some((data_json, excel_paths)) => (Some(data_json), Some(excel_paths)),
+            None => (self.data_json.clone(), self.excel_paths.clone()),
+        };
+
+        let selected = match (&self.schema_registry, &self.schema_bundle) {
+            (Some(dir), Some(name)) => Some(SchemaRegistry::load(dir)?.select_by_name(name)?),
+            (Some(dir), None) => {
+                let revision_id = downloader::fetch_mini_asset(source)
+                    .context("Failed to download mini asset")?
+                    .revision_id;
+                Some(SchemaRegistry::load(dir)?.select(revision_id)?)
+            }
+            (None, Some(_)) => anyhow::bail!("--schema-bundle needs --schema-registry"),
+            (None, None) => None,
+        };
+        let bundle = selected.as_ref().map(|selected| &selected.bundle);
+
+        Ok(SchemaFiles {
+            data_json: data_json
+                .or_else(|| bundle.map(SchemaBundle::data_json))
+                .context("--data-json is required without --schema-registry")?,
+            excel_paths: excel_paths
+                .or_else(|| bundle.map(SchemaBundle::excel_paths))
+                .context("--excel-paths is required without --schema-registry")?,
+            config_paths: self
+                .config_paths
+                .clone()
+                .or_else(|| bundle.and_then(SchemaBundle::config_paths)),
+            custom_parsers: self
+                .custom_parsers
+                .clone()
+                .or_else(|| bundle.and_then(SchemaBundle::custom_parsers)),
+            bundle: selected,
+        })
+    }
+
+    pub fn parse_options(&self, schema: &SchemaFiles) -> Result<ParseOptions> {
+        let layouts = load_layouts(&schema.data_json, schema.custom_parsers.as_deref())?;
+
+        Ok(ParseOptions {
+            probe_typeindex: self.probe_typeindex,
+            enum_with_value: self.enum_with_value,
+            dictionary_mode: self.dictionary_mode.into(),
+            dictionary_modes: self
+                .dictionary_mode_for
+                .iter()
+                .map(|(type_name, mode)| (type_name.clone(), (*mode).into()))
+                .collect(),
+            type_names: TypeNamePolicy {
+                emit: self.type_emit.into(),
+                key: self.type_key.clone(),
+                strip_prefixes: self.strip_type_prefix.clone(),
+                strip_suffixes: self.strip_type_suffix.clone(),
+                qualified: !self.short_type_names,
+            },
+            layouts,
+        })
+    }
+}
+
+/// Opens `input_url` followed by every overlay, lowest priority first.
+pub fn open_layers(
+    input_url: &str,
+    overlays: &[String],
+    sources: &SourceArgs,
+) -> Result<Vec<Box<dyn AssetSource>>> {
+    std::iter::once(input_url)
+        .chain(overlays.iter().map(String::as_str))
+        .map(|layer| sources.open(layer))
+        .collect()
+}
+
+/// Loads the declarative custom parsers, from custom_parsers.json next to
+/// data.json unless a path is given.
+pub fn load_layouts(data_json: &Path, custom_parsers: Option<&Path>) -> Result<Layouts> {
+    let layouts_path = custom_parsers
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| data_json.with_file_name("custom_parsers.json"));
+
+    if layouts_path.is_file() {
+        serde_json::from_slice(&fs::read(&layouts_path).context("Failed to read custom parsers")?)
+            .context("Failed to parse custom parsers")
+    } else if custom_parsers.is_some() {
+        anyhow::bail!("custom parsers file {layouts_path:?} not found");
+    } else {
+        Ok(Layouts::new())
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SchemaFormat {
+    /// ImHex pattern
+    Hexpat,
+    /// Kaitai Struct
+    Ksy,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PlatformArg {
+    Windows,
+    Android,
+    Ios,
+}
+
+impl From<PlatformArg> for Platform {
+    fn from(value: PlatformArg) -> Self {
+        match value {
+            PlatformArg::Windows => Platform::Windows,
+            PlatformArg::Android => Platform::Android,
+            PlatformArg::Ios => Platform::Ios,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ListFormat {
+    /// Blocks with their entries indented
+    Table,
+    Json,
+    /// One row per entry
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum IndexKindArg {
+    Lua,
+    Video,
+    Audio,
+}
+
+impl From<IndexKindArg> for IndexKind {
+    fn from(value: IndexKindArg) -> Self {
+        match value {
+            IndexKindArg::Lua => IndexKind::Lua,
+            IndexKindArg::Video => IndexKind::Video,
+            IndexKindArg::Audio => IndexKind::Audio,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DictionaryModeArg {
+    /// JSON object, non-string keys are stringified
+    Object,
+    /// [{"Key": ..., "Value": ...}] array, keys keep their type
+    KeyValueArray,
+}
+
+impl From<DictionaryModeArg> for DictionaryMode {
+    fn from(value: DictionaryModeArg) -> Self {
+        match value {
+            DictionaryModeArg::Object => DictionaryMode::Object,
+            DictionaryModeArg::KeyValueArray => DictionaryMode::KeyValueArray,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TypeEmitArg {
+    /// Only on classes reached through a Typeindex
+    Polymorphic,
+    /// On every class
+    Always,
+    /// Never
+    Never,
+}
+
+impl From<TypeEmitArg> for TypeEmit {
+    fn from(value: TypeEmitArg) -> Self {
+        match value {
+            TypeEmitArg::Polymorphic => TypeEmit::Polymorphic,
+            TypeEmitArg::Always => TypeEmit::Always,
+            TypeEmitArg::Never => TypeEmit::Never,
+        }
+    }
+}
+
+/// Parses a byte size with an optional K, M or G suffix.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let (digits, unit) = match s.trim_end_matches(['B', 'b']).char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_uppercase()),
+        _ => (s.trim_end_matches(['B', 'b']), ' '),
+    };
+    let shift = match unit {
+        ' ' => 0,
+        'K' => 10,
+        'M' => 20,
+        'G' => 30,
+        _ => return Err(format!("unknown size unit in {s}")),
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .map(|n| n << shift)
+        .map_err(|e| format!("invalid size {s}: {e}"))
+}
+
+fn parse_type_dictionary_mode(s: &str) -> Result<(String, DictionaryModeArg), String> {
+    let (type_name, mode) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected TYPE=MODE, got {s}"))?;
+    Ok((
+        type_name.to_string(),
+        DictionaryModeArg::from_str(mode, true)?,
+    ))
 }
@@ -8,19 +8,26 @@ use common::downloader;
 use std::{
     collections::HashMap,
     fs,
-    sync::atomic::{AtomicI32, Ordering},
+    path::Path,
+    sync::{
+        OnceLock,
+        atomic::{AtomicI32, Ordering},
+    },
     time::Instant,
 };
-use tg_parser::DataDefine;
+use tg_parser::{DataDefine, ParseOptions};
 use tracing::Level;
 
 mod actions;
 mod cli;
+mod registry;
 
 pub static COUNTER_CONFIGS: AtomicI32 = AtomicI32::new(0);
 pub static COUNTER_EXCELS: AtomicI32 = AtomicI32::new(0);
 pub static COUNTER_TEXTMAPS: AtomicI32 = AtomicI32::new(0);
 
+pub static PARSE_OPTIONS: OnceLock<ParseOptions> = OnceLock::new();
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
@@ -30,13 +37,16 @@ fn main() -> Result<()> {
             output_dir,
             full_textmap,
             save_bytes_file,
+            overlay,
+            sources,
         } => {
             common::logging::init(Level::INFO);
 
             let start = Instant::now();
 
-            let assets = downloader::download_all_design_data(
-                input_url.clone(),
+            let layers = cli::open_layers(input_url, overlay, sources)?;
+            let data = downloader::download_layered_design_data(
+                &layers.iter().map(Box::as_ref).collect::<Vec<_>>(),
                 if *save_bytes_file {
                     Some(output_dir.clone())
                 } else {
@@ -44,58 +54,108 @@ fn main() -> Result<()> {
                 },
                 TEXTMAP_PATHS.iter().map(|v| v.1).collect(),
             )?;
+            actions::report::write_layers(output_dir, &layers, &data)?;
+            let assets = data.assets;
 
             tracing::info!("Download Done! Took {}s", start.elapsed().as_secs());
 
             let start = Instant::now();
 
             actions::textmap::parse_all_textmap(&assets, output_dir, !full_textmap)?;
+            actions::report::write(output_dir, true)?;
+            sources.prune()?;
 
             tracing::info!("Textmap Parse Done! Took {}ms", start.elapsed().as_millis());
         }
 
-        Command::Excels(args) | Command::All(args) => {
+        Command::Excels(args) | Command::All(args) | Command::Update(args) => {
             if args.log_error {
                 common::logging::init(Level::INFO)
             } else {
                 common::logging::init_info_only();
             }
 
-            let assets = downloader::download_all_design_data(
-                args.input_url.clone(),
-                if args.save_bytes_file {
-                    Some(args.output_dir.clone())
-                } else {
-                    None
-                },
-                Vec::with_capacity(0),
-            )?;
+            let update = matches!(cli.command, Command::Update(_));
+            if update && !args.overlay.is_empty() {
+                anyhow::bail!("update doesn't support --overlay yet, use all");
+            }
+
+            let (input_url, output_dir) = args.input_output()?;
+            let layers = cli::open_layers(input_url, &args.overlay, &args.sources)?;
+            // the top layer has the revision being parsed
+            let schema = args.schema_files(layers.last().unwrap().as_ref())?;
+            if let Some(selected) = &schema.bundle {
+                match selected.game_revision_id {
+                    Some(revision_id) => tracing::info!(
+                        "Using schema bundle {} ({:?}) for revision {revision_id}",
+                        selected.bundle.name,
+                        selected.selection,
+                    ),
+                    None => tracing::info!("Using schema bundle {}", selected.bundle.name),
+                }
+            }
+
+            let _ = PARSE_OPTIONS.set(args.parse_options(&schema)?);
+
+            let save_bytes_folder = args.save_bytes_file.then(|| output_dir.to_path_buf());
+            let data = match (&cli.command, layers.as_slice()) {
+                (Command::Update(_), _) => actions::update::fetch_changed(
+                    layers[0].as_ref(),
+                    output_dir,
+                    save_bytes_folder.as_deref(),
+                )?,
+                // a full run is what the next update compares against
+                (Command::All(_), [source]) => actions::update::fetch_all(
+                    source.as_ref(),
+                    output_dir,
+                    save_bytes_folder.as_deref(),
+                )?,
+                _ => downloader::download_layered_design_data(
+                    &layers.iter().map(Box::as_ref).collect::<Vec<_>>(),
+                    save_bytes_folder,
+                    Vec::with_capacity(0),
+                )?,
+            };
+            actions::report::write_layers(output_dir, &layers, &data)?;
+
+            if update && data.assets.is_empty() && data.failed.is_empty() {
+                tracing::info!("Nothing changed since the last run");
+                actions::update::commit(output_dir, &data)?;
+                return Ok(());
+            }
+            let assets = &data.assets;
 
             let start = Instant::now();
 
             let excel_paths: HashMap<String, Vec<String>> = serde_json::from_slice(
-                &fs::read(&args.excel_path_json).context("Failed to read excel_paths.json")?,
+                &fs::read(&schema.excel_paths).context("Failed to read excel_paths.json")?,
             )?;
 
             let types: HashMap<String, DataDefine> = serde_json::from_slice(
-                &fs::read(&args.data_json).context("Failed to read data.json")?,
+                &fs::read(&schema.data_json).context("Failed to read data.json")?,
             )?;
 
-            actions::excel::parse_all_excels(
-                &assets,
-                &types,
-                &args.output_dir.clone(),
-                &excel_paths,
-            )?;
+            actions::excel::parse_all_excels(assets, &types, output_dir, &excel_paths)?;
 
-            if let Command::All(_) = cli.command {
+            if let Command::All(_) | Command::Update(_) = cli.command {
                 actions::config::parse_configs(
-                    &assets,
+                    assets,
                     &types,
-                    &args.output_dir,
-                    args.config_paths.clone(),
+                    output_dir,
+                    schema.config_paths.clone(),
                 )?;
-                actions::textmap::parse_all_textmap(&assets, &args.output_dir, !args.full_textmap)?;
+                actions::textmap::parse_all_textmap(assets, output_dir, !args.full_textmap)?;
+            }
+
+            actions::report::write(output_dir, update)?;
+            if let (Command::All(_), [_]) | (Command::Update(_), _) =
+                (&cli.command, layers.as_slice())
+            {
+                actions::update::commit(output_dir, &data)?;
+            }
+            args.sources.prune()?;
+            if let Some(selected) = &schema.bundle {
+                selected.write(output_dir)?;
             }
 
             tracing::info!(
@@ -105,37 +165,236 @@ fn main() -> Result<()> {
                 COUNTER_TEXTMAPS.load(Ordering::Relaxed),
                 start.elapsed().as_secs()
             );
+            data.ensure_complete()?;
+        }
+
+        Command::GuessType {
+            data_json,
+            asset,
+            input_url,
+            top,
+            custom_parsers,
+            sources,
+        } => {
+            common::logging::init(Level::INFO);
+
+            let bytes = actions::load_asset(asset, input_url.as_deref(), sources)?;
+            let types: HashMap<String, DataDefine> =
+                serde_json::from_slice(&fs::read(data_json).context("Failed to read data.json")?)?;
+            let options = ParseOptions {
+                layouts: cli::load_layouts(Path::new(data_json), custom_parsers.as_deref())?,
+                ..Default::default()
+            };
+
+            actions::guess_type::print_guesses(&types, &options, &bytes, *top)?;
+            sources.prune()?;
+        }
+
+        Command::GenSchema {
+            dump_cs,
+            output_dir,
+            hints,
+        } => {
+            common::logging::init(Level::INFO);
+
+            actions::gen_schema::gen_schema(dump_cs, output_dir, hints.as_deref())?;
+        }
+
+        Command::DiscoverExcels {
+            data_json,
+            input_url,
+            output_dir,
+            custom_parsers,
+            sources,
+        } => {
+            common::logging::init_info_only();
+
+            let _ = PARSE_OPTIONS.set(ParseOptions {
+                layouts: cli::load_layouts(Path::new(data_json), custom_parsers.as_deref())?,
+                ..Default::default()
+            });
+
+            let types: HashMap<String, DataDefine> =
+                serde_json::from_slice(&fs::read(data_json).context("Failed to read data.json")?)?;
+            let source = sources.open(input_url)?;
+
+            actions::discover_excels::discover_excels(source.as_ref(), &types, output_dir)?;
+            sources.prune()?;
+        }
+
+        Command::Verify { path, platform } => {
+            common::logging::init(Level::INFO);
+
+            actions::verify::verify(path, (*platform).into())?;
+        }
+
+        Command::Ls {
+            input_url,
+            paths,
+            min_size,
+            max_size,
+            path,
+            block,
+            format,
+            sources,
+        } => {
+            common::logging::init_stderr(Level::INFO);
+
+            let known = actions::known_paths::KnownPaths::load(paths)?;
+            tracing::info!("{} known paths", known.len());
+            let source = sources.open(input_url)?;
+            let design_index = downloader::fetch_design_index(source.as_ref(), None)?;
+            let files = actions::ls::select(
+                &design_index,
+                &known,
+                &actions::ls::LsFilter {
+                    min_size: *min_size,
+                    max_size: *max_size,
+                    path: path.clone(),
+                    block: block.clone(),
+                },
+            );
+            actions::ls::print(&files, *format)?;
+            sources.prune()?;
+        }
+
+        Command::Extract {
+            input_url,
+            output_dir,
+            assets,
+            paths,
+            sources,
+        } => {
+            common::logging::init(Level::INFO);
+
+            let known = actions::known_paths::KnownPaths::load(paths)?;
+            let selected = actions::extract::resolve(assets, &known)?;
+            let source = sources.open(input_url)?;
+            actions::extract::extract(source.as_ref(), &selected, output_dir)?;
+            sources.prune()?;
+        }
+
+        Command::Assets {
+            input_url,
+            kind,
+            filter,
+            download,
+            json,
+            sources,
+        } => {
+            common::logging::init_stderr(Level::INFO);
+
+            let source = sources.open(input_url)?;
+            actions::assets::assets(
+                source.as_ref(),
+                (*kind).into(),
+                filter.as_deref(),
+                download.as_deref(),
+                *json,
+            )?;
+            sources.prune()?;
+        }
+
+        Command::Repack {
+            input_url,
+            output_dir,
+            replace,
+            revision,
+            sources,
+        } => {
+            common::logging::init(Level::INFO);
+
+            let source = sources.open(input_url)?;
+            actions::repack::repack(source.as_ref(), replace.as_deref(), output_dir, *revision)?;
+            sources.prune()?;
+        }
+
+        Command::Meta {
+            input_url,
+            json,
+            sources,
+        } => {
+            common::logging::init_stderr(Level::INFO);
+
+            if Path::new(input_url).is_file() && input_url.ends_with(".bytes") {
+                actions::meta::print_meta(input_url, None, *json)?;
+            } else {
+                let source = sources.open(input_url)?;
+                actions::meta::print_meta(input_url, Some(source.as_ref()), *json)?;
+                sources.prune()?;
+            }
+        }
+
+        Command::ComparePlatforms {
+            input_url,
+            other,
+            output,
+            other_url,
+            sources,
+        } => {
+            common::logging::init(Level::INFO);
+
+            let a = sources.open(input_url)?;
+            let b = sources
+                .open_platform(other_url.as_deref().unwrap_or(input_url), (*other).into())?;
+            actions::compare_platforms::compare_platforms(
+                a.as_ref(),
+                b.as_ref(),
+                output.as_deref(),
+            )?;
+            sources.prune()?;
+        }
+
+        Command::Explain {
+            data_json,
+            asset,
+            root_type,
+            input_url,
+            array,
+            trace_json,
+            custom_parsers,
+            sources,
+        } => {
+            common::logging::init(Level::INFO);
+
+            let bytes = actions::load_asset(asset, input_url.as_deref(), sources)?;
+            let types: HashMap<String, DataDefine> =
+                serde_json::from_slice(&fs::read(data_json).context("Failed to read data.json")?)?;
+            let options = ParseOptions {
+                layouts: cli::load_layouts(Path::new(data_json), custom_parsers.as_deref())?,
+                ..Default::default()
+            };
+
+            actions::explain::explain(
+                &types,
+                &options,
+                &bytes,
+                root_type,
+                *array,
+                trace_json.as_deref(),
+            )?;
+            sources.prune()?;
+        }
+
+        Command::ExportSchema {
+            data_json,
+            root_type,
+            output,
+            format,
+            custom_parsers,
+        } => {
+            common::logging::init(Level::INFO);
+
+            let types: HashMap<String, DataDefine> =
+                serde_json::from_slice(&fs::read(data_json).context("Failed to read data.json")?)?;
+            let options = ParseOptions {
+                layouts: cli::load_layouts(Path::new(data_json), custom_parsers.as_deref())?,
+                ..Default::default()
+            };
+
+            actions::export_schema::export(&types, &options, root_type, output, *format)?;
         }
     }
 
     Ok(())
 }
-
-// fn main() {
-//     use std::collections::HashMap;
-//     use tg_parser::{DynamicParser, ValueKind};
-//     let assets = common::downloader::download_all_design_data(
-//         String::from(
-//             "C:/Data/hoyoreverse/StarRail_3.3.51/StarRail_Data/Persistent/DesignData/Windows",
-//         ),
-//         None,
-//     )
-//     .unwrap();
-//     common::logging::init(tracing::Level::DEBUG);
-//     let bytes = assets
-//         .get(&common::hash::get_32bit_hash(
-//             "BakedConfig/Config/AudioConfig.bytes",
-//         ))
-//         .unwrap();
-//     let bytes = &bytes[12..].to_vec();
-//     let schema: HashMap<String, DataDefine> =
-//         serde_json::from_slice(&std::fs::read("data.json").unwrap()).unwrap();
-//     let mut parser = DynamicParser::new(&schema, &bytes);
-//     let parsed = parser
-//         .parse(
-//             &ValueKind::Class(String::from("RPG.GameCore.AudioConfig")),
-//             false,
-//         )
-//         .unwrap();
-//     std::fs::write("ss.json", serde_json::to_string_pretty(&parsed).unwrap()).unwrap();
-// }
@@ -0,0 +1,207 @@
+//! Schema bundles, one directory per game revision:
+//!
+//! ```text
+//! schemas/
+//!   8945211/               <- revision_id, or any name with a bundle.json
+//!     data.json
+//!     excel_paths.json
+//!     config_paths.json    (optional)
+//!     custom_parsers.json  (optional)
+//!     bundle.json          (optional) {"revision_id": 8945211}
+//! ```
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SchemaBundle {
+    pub name: String,
+    pub path: PathBuf,
+    /// Revision the bundle was made for, bundles without one are only used
+    /// when picked by name.
+    pub revision_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct BundleMeta {
+    revision_id: Option<u32>,
+}
+
+impl SchemaBundle {
+    fn load(path: PathBuf) -> Result<Self> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let meta_path = path.join("bundle.json");
+        let revision_id = if meta_path.is_file() {
+            let meta: BundleMeta = serde_json::from_slice(&fs::read(&meta_path)?)
+                .context(format!("Failed to parse {meta_path:?}"))?;
+            meta.revision_id
+        } else {
+            name.parse().ok()
+        };
+
+        Ok(Self {
+            name,
+            path,
+            revision_id,
+        })
+    }
+
+    pub fn data_json(&self) -> PathBuf {
+        self.path.join("data.json")
+    }
+
+    pub fn excel_paths(&self) -> PathBuf {
+        self.path.join("excel_paths.json")
+    }
+
+    pub fn config_paths(&self) -> Option<PathBuf> {
+        Some(self.path.join("config_paths.json")).filter(|path| path.is_file())
+    }
+
+    pub fn custom_parsers(&self) -> Option<PathBuf> {
+        Some(self.path.join("custom_parsers.json")).filter(|path| path.is_file())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum Selection {
+    /// Picked by name on the command line.
+    Explicit,
+    /// Made for the exact revision being parsed.
+    Exact,
+    /// The closest older bundle, or the closest newer one if there's none.
+    Nearest,
+}
+
+/// A bundle chosen for a run, as written to `SchemaBundle.json`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SelectedBundle {
+    pub bundle: SchemaBundle,
+    pub selection: Selection,
+    /// Revision of the game data, not looked up for an explicit bundle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_revision_id: Option<u32>,
+}
+
+impl SelectedBundle {
+    pub fn write(&self, out_folder: &Path) -> Result<()> {
+        fs::write(
+            out_folder.join("SchemaBundle.json"),
+            serde_json::to_string_pretty(self)?,
+        )
+        .context("Failed to write SchemaBundle.json")
+    }
+}
+
+pub struct SchemaRegistry {
+    bundles: Vec<SchemaBundle>,
+}
+
+impl SchemaRegistry {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut bundles = Vec::new();
+        for entry in fs::read_dir(dir).context(format!("Failed to read registry {dir:?}"))? {
+            let path = entry?.path();
+            if path.join("data.json").is_file() {
+                bundles.push(SchemaBundle::load(path)?);
+            }
+        }
+        bundles.sort_by_key(|bundle| bundle.revision_id);
+
+        Ok(Self { bundles })
+    }
+
+    pub fn select(&self, game_revision_id: u32) -> Result<SelectedBundle> {
+        let (bundle, selection) = self
+            .select_by_revision(game_revision_id)
+            .context("Schema registry has no bundle with a revision id")?;
+
+        Ok(SelectedBundle {
+            bundle: bundle.clone(),
+            selection,
+            game_revision_id: Some(game_revision_id),
+        })
+    }
+
+    pub fn select_by_name(&self, name: &str) -> Result<SelectedBundle> {
+        let bundle = self
+            .bundles
+            .iter()
+            .find(|bundle| bundle.name == name)
+            .context(format!("Schema bundle {name} not found in registry"))?;
+
+        Ok(SelectedBundle {
+            bundle: bundle.clone(),
+            selection: Selection::Explicit,
+            game_revision_id: None,
+        })
+    }
+
+    fn select_by_revision(&self, revision_id: u32) -> Option<(&SchemaBundle, Selection)> {
+        let versioned = self
+            .bundles
+            .iter()
+            .filter_map(|bundle| Some((bundle.revision_id?, bundle)));
+
+        let mut older = None;
+        let mut newer = None;
+        for (bundle_revision, bundle) in versioned {
+            if bundle_revision == revision_id {
+                return Some((bundle, Selection::Exact));
+            } else if bundle_revision < revision_id {
+                older = Some(bundle);
+            } else if newer.is_none() {
+                newer = Some(bundle);
+            }
+        }
+
+        older.or(newer).map(|bundle| (bundle, Selection::Nearest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SchemaBundle, SchemaRegistry, Selection};
+    use std::path::PathBuf;
+
+    fn registry(revisions: &[u32]) -> SchemaRegistry {
+        SchemaRegistry {
+            bundles: revisions
+                .iter()
+                .map(|revision| SchemaBundle {
+                    name: revision.to_string(),
+                    path: PathBuf::from(revision.to_string()),
+                    revision_id: Some(*revision),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_select_bundle() {
+        let registry = registry(&[100, 200, 300]);
+
+        let selected = registry.select(200).unwrap();
+        assert_eq!(selected.bundle.name, "200");
+        assert!(matches!(selected.selection, Selection::Exact));
+
+        let selected = registry.select(250).unwrap();
+        assert_eq!(selected.bundle.name, "200");
+        assert!(matches!(selected.selection, Selection::Nearest));
+
+        assert_eq!(registry.select(50).unwrap().bundle.name, "100");
+        assert_eq!(registry.select_by_name("300").unwrap().bundle.name, "300");
+        assert!(registry.select_by_name("400").is_err());
+    }
+}